@@ -0,0 +1,49 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+// Cooperative cancellation for long running solvers. The runner arms a deadline before each
+// part, and solvers call tick() once per state they explore. Once it returns true the solver
+// should stop and return whatever it has found so far.
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+    static STATES: Cell<u64> = const { Cell::new(0) };
+    static CANCELLED: Cell<bool> = const { Cell::new(false) };
+}
+
+// Reading the clock on every state is measurably slow in the tight loops, so only check the
+// deadline every so often.
+static CHECK_INTERVAL: u64 = 64;
+
+pub fn arm(timeout: Option<Duration>) {
+    DEADLINE.set(timeout.map(|t| Instant::now() + t));
+    STATES.set(0);
+    CANCELLED.set(false);
+}
+
+// Returns the number of states explored if the part was cancelled.
+pub fn disarm() -> Option<u64> {
+    DEADLINE.set(None);
+    return if CANCELLED.get() {
+        Some(STATES.get())
+    } else {
+        None
+    };
+}
+
+pub fn tick() -> bool {
+    let states = STATES.get() + 1;
+    STATES.set(states);
+
+    if !CANCELLED.get() && states % CHECK_INTERVAL == 0 {
+        if let Some(deadline) = DEADLINE.get() {
+            CANCELLED.set(Instant::now() >= deadline);
+        }
+    }
+    return CANCELLED.get();
+}
+
+pub fn is_cancelled() -> bool {
+    return CANCELLED.get();
+}
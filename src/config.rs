@@ -0,0 +1,152 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::OnceLock};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+// Config file picked up from the working directory when --config is not passed.
+static DEFAULT_CONFIG_FILE: &str = "aoc.toml";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub input_dir: PathBuf,
+    pub verbose: bool,
+    pub color: ColorMode,
+    pub part: Option<u32>,
+    pub explain: bool,
+    pub format: OutputFormat,
+    pub json_file: Option<PathBuf>, // JSON reports go to stderr when unset
+    pub max_memory: Option<String>,
+    pub timeout: Option<f64>, // seconds
+
+    // Per-day parameter overrides, keyed by table name ("day14" -> { board_size = [11, 7] })
+    #[serde(flatten)]
+    pub days: HashMap<String, toml::Table>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        return Self {
+            input_dir: PathBuf::from("input"),
+            verbose: false,
+            color: ColorMode::Auto,
+            part: None,
+            explain: false,
+            format: OutputFormat::Text,
+            json_file: None,
+            max_memory: None,
+            timeout: None,
+            days: HashMap::new(),
+        };
+    }
+}
+
+impl Config {
+    pub fn load(config_file: Option<&PathBuf>) -> Self {
+        let path = config_file
+            .cloned()
+            .unwrap_or(PathBuf::from(DEFAULT_CONFIG_FILE));
+
+        if !path.exists() {
+            if config_file.is_some() {
+                panic!("Could not find config file: {}", path.to_string_lossy());
+            }
+            // No project level config, fall back to the built-in defaults.
+            return Self::default();
+        }
+
+        let contents = fs::read_to_string(&path)
+            .expect(format!("Could not read config file: {}", path.to_string_lossy()).as_str());
+        return toml::from_str(&contents)
+            .unwrap_or_else(|e| panic!("Invalid config file {}: {}", path.to_string_lossy(), e));
+    }
+
+    pub fn set_param(&mut self, day: u32, key: &str, value: toml::Value) {
+        self.days
+            .entry(format!("day{}", day))
+            .or_default()
+            .insert(key.to_string(), value);
+    }
+
+    pub fn apply_override(&mut self, spec: &str) {
+        // Overrides look like "day14.board_size=[11, 7]". The value is parsed as a TOML value,
+        // and falls back to a plain string so "day1.metric=emd" works without quotes.
+        let (key, value) = spec
+            .split_once("=")
+            .expect(format!("Invalid override '{}', expected dayN.key=value", spec).as_str());
+        let (day, key) = key
+            .trim()
+            .split_once(".")
+            .expect(format!("Invalid override '{}', expected dayN.key=value", spec).as_str());
+        let day: u32 = day
+            .trim_start_matches("day")
+            .parse()
+            .expect(format!("Invalid day in override '{}'", spec).as_str());
+
+        let value = value.trim();
+        let parsed = toml::from_str::<toml::Table>(format!("v = {}", value).as_str())
+            .ok()
+            .and_then(|mut t| t.remove("v"))
+            .unwrap_or(toml::Value::String(value.to_string()));
+
+        self.set_param(day, key.trim(), parsed);
+    }
+
+    pub fn input_for_day(&self, day: u32) -> PathBuf {
+        return self.input_dir.join(format!("day{}.txt", day));
+    }
+
+    pub fn apply_color(&self) {
+        match self.color {
+            ColorMode::Auto => (),
+            ColorMode::Always => colored::control::set_override(true),
+            ColorMode::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+pub fn init(config: Config) {
+    config.apply_color();
+    CONFIG.set(config).expect("Config initialized twice");
+}
+
+pub fn get() -> &'static Config {
+    return CONFIG.get_or_init(Config::default);
+}
+
+pub fn default_input(day: u32) -> PathBuf {
+    return get().input_for_day(day);
+}
+
+pub fn day_param<T: DeserializeOwned>(day: u32, key: &str, default: T) -> T {
+    let value = get()
+        .days
+        .get(&format!("day{}", day))
+        .and_then(|table| table.get(key));
+
+    return match value {
+        None => default,
+        Some(value) => value
+            .clone()
+            .try_into()
+            .unwrap_or_else(|e| panic!("Invalid value for day{}.{}: {} ({})", day, key, value, e)),
+    };
+}
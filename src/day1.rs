@@ -1,100 +1,504 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 
+use crate::{config, explain::Explanation, metrics, runner};
+
 pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day1.txt"));
+    let input = input_opt.unwrap_or(config::default_input(1));
+    runner::run_parts(1, part_opt, || part1(&input), || part2(&input));
+}
+
+static QUANTILES: [f64; 5] = [0.1, 0.25, 0.5, 0.75, 0.9];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Metric {
+    Distance,   // Sum of differences between the sorted lists (part 1)
+    Similarity, // Frequency weighted similarity score (part 2)
+    Emd,        // Earth mover's distance between the two value distributions
+    Jaccard,    // Overlap of the distinct values
+    Overlap,    // Overlap of the values, counting repeats (multiset Jaccard)
+    Spearman,   // Rank correlation of the row-wise pairs
+    Kendall,    // Kendall tau-b of the row-wise pairs
+}
+
+static ALL_METRICS: [Metric; 7] = [
+    Metric::Distance,
+    Metric::Similarity,
+    Metric::Emd,
+    Metric::Jaccard,
+    Metric::Overlap,
+    Metric::Spearman,
+    Metric::Kendall,
+];
+
+impl Metric {
+    fn from_str(s: &str) -> Self {
+        return match s.trim().to_lowercase().as_str() {
+            "distance" => Metric::Distance,
+            "similarity" => Metric::Similarity,
+            "emd" => Metric::Emd,
+            "jaccard" => Metric::Jaccard,
+            "overlap" => Metric::Overlap,
+            "spearman" => Metric::Spearman,
+            "kendall" => Metric::Kendall,
+            _ => panic!("Unknown metric: {}", s),
+        };
+    }
+
+    fn parse_list(s: &str) -> Vec<Self> {
+        if s.trim() == "all" {
+            return ALL_METRICS.to_vec();
+        }
+        return s.split(",").map(Metric::from_str).collect();
+    }
+
+    fn needs_rows(&self) -> bool {
+        return *self == Metric::Spearman || *self == Metric::Kendall;
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Metric::Distance => "distance",
+            Metric::Similarity => "similarity",
+            Metric::Emd => "emd",
+            Metric::Jaccard => "jaccard",
+            Metric::Overlap => "overlap",
+            Metric::Spearman => "spearman",
+            Metric::Kendall => "kendall",
+        };
+        return f.pad(name);
+    }
+}
+
+#[derive(Debug)]
+struct LocationLists {
+    // Raw columns, only kept around when not streaming so they can be printed.
+    columns: Vec<Vec<i64>>,
+    // value -> number of occurrences, per column. Everything is computed from these so that
+    // streaming mode never has to hold the full lists in memory.
+    counts: Vec<BTreeMap<i64, u64>>,
+    num_rows: u64,
+}
+
+impl LocationLists {
+    fn from_file(input_file: &PathBuf, streaming: bool) -> Self {
+        let input_file = File::open(input_file).expect(
+            format!(
+                "Could not open input file: {}",
+                input_file.to_string_lossy()
+            )
+            .as_str(),
+        );
+        let lines = BufReader::new(input_file).lines();
+
+        let mut columns: Vec<Vec<i64>> = Vec::new();
+        let mut counts: Vec<BTreeMap<i64, u64>> = Vec::new();
+        let mut num_rows: u64 = 0;
+
+        for line in lines {
+            let line = line.expect("Could not read line");
+            metrics::incr("lines_read");
+            let values: Vec<i64> = line
+                .split_ascii_whitespace()
+                .map(|s| {
+                    s.parse::<i64>()
+                        .expect(format!("Could not parse location id {}", s).as_str())
+                })
+                .collect();
+            if values.is_empty() {
+                continue;
+            }
+
+            if counts.is_empty() {
+                counts = vec![BTreeMap::new(); values.len()];
+                columns = vec![Vec::new(); values.len()];
+            }
+            if values.len() != counts.len() {
+                panic!(
+                    "Expected {} columns, found {} in line: {}",
+                    counts.len(),
+                    values.len(),
+                    line
+                );
+            }
+
+            for (col, val) in values.into_iter().enumerate() {
+                *counts[col].entry(val).or_insert(0) += 1;
+                if !streaming {
+                    columns[col].push(val);
+                }
+            }
+            num_rows += 1;
+        }
+
+        if counts.len() < 2 {
+            panic!("Need at least two location lists to compare");
+        }
+
+        return Self {
+            columns,
+            counts,
+            num_rows,
+        };
+    }
+
+    fn num_columns(&self) -> usize {
+        return self.counts.len();
+    }
+
+    fn column_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for a in 0..self.num_columns() - 1 {
+            for b in a + 1..self.num_columns() {
+                pairs.push((a, b));
+            }
+        }
+        return pairs;
+    }
+
+    fn distance(&self, a: usize, b: usize) -> i64 {
+        // Pairs up the k-th smallest values of both lists by walking the sorted counts, which
+        // is the same as sorting both lists and zipping them.
+        let mut iter_a = self.counts[a].iter();
+        let mut iter_b = self.counts[b].iter();
+        let mut curr_a = iter_a.next().map(|(v, c)| (*v, *c));
+        let mut curr_b = iter_b.next().map(|(v, c)| (*v, *c));
+
+        let mut sum: i64 = 0;
+        while let (Some((val_a, count_a)), Some((val_b, count_b))) = (curr_a, curr_b) {
+            let paired = count_a.min(count_b);
+            sum += (val_a - val_b).abs() * paired as i64;
+
+            curr_a = if count_a == paired {
+                iter_a.next().map(|(v, c)| (*v, *c))
+            } else {
+                Some((val_a, count_a - paired))
+            };
+            curr_b = if count_b == paired {
+                iter_b.next().map(|(v, c)| (*v, *c))
+            } else {
+                Some((val_b, count_b - paired))
+            };
+        }
+        return sum;
+    }
+
+    fn similarity(&self, a: usize, b: usize) -> i64 {
+        return self.counts[a]
+            .iter()
+            .map(|(val, count)| val * (*count * self.counts[b].get(val).unwrap_or(&0)) as i64)
+            .sum();
+    }
+
+    fn metric(&self, metric: Metric, a: usize, b: usize) -> f64 {
+        return match metric {
+            Metric::Distance => self.distance(a, b) as f64,
+            Metric::Similarity => self.similarity(a, b) as f64,
+            Metric::Emd => self.earth_movers_distance(a, b),
+            Metric::Jaccard => self.jaccard(a, b),
+            Metric::Overlap => self.multiset_overlap(a, b),
+            Metric::Spearman => spearman(&self.columns[a], &self.columns[b]),
+            Metric::Kendall => kendall_tau_b(&self.columns[a], &self.columns[b]),
+        };
+    }
+
+    fn earth_movers_distance(&self, a: usize, b: usize) -> f64 {
+        // Area between the two cumulative distributions. Works on the counts directly, so the
+        // lists don't need to be sorted (or even be the same length).
+        let total_a: u64 = self.counts[a].values().sum();
+        let total_b: u64 = self.counts[b].values().sum();
+
+        let mut values: Vec<&i64> = self.counts[a].keys().chain(self.counts[b].keys()).collect();
+        values.sort();
+        values.dedup();
+
+        let mut cdf_a: f64 = 0.0;
+        let mut cdf_b: f64 = 0.0;
+        let mut emd: f64 = 0.0;
+        for window in values.windows(2) {
+            cdf_a += *self.counts[a].get(window[0]).unwrap_or(&0) as f64 / total_a as f64;
+            cdf_b += *self.counts[b].get(window[0]).unwrap_or(&0) as f64 / total_b as f64;
+            emd += (cdf_a - cdf_b).abs() * (window[1] - window[0]) as f64;
+        }
+        return emd;
+    }
+
+    fn jaccard(&self, a: usize, b: usize) -> f64 {
+        let intersection = self.counts[a]
+            .keys()
+            .filter(|v| self.counts[b].contains_key(v))
+            .count();
+        let union = self.counts[a].len() + self.counts[b].len() - intersection;
+        return intersection as f64 / union as f64;
+    }
+
+    fn multiset_overlap(&self, a: usize, b: usize) -> f64 {
+        let mut min_sum: u64 = 0;
+        let mut max_sum: u64 = 0;
+        for (val, count_a) in self.counts[a].iter() {
+            let count_b = *self.counts[b].get(val).unwrap_or(&0);
+            min_sum += count_a.min(&count_b);
+            max_sum += count_a.max(&count_b);
+        }
+        for (val, count_b) in self.counts[b].iter() {
+            if !self.counts[a].contains_key(val) {
+                max_sum += count_b;
+            }
+        }
+        return min_sum as f64 / max_sum as f64;
+    }
 
-    if part_opt.is_none() {
-        println!("Running Day 1, Part 1");
-        part1(&input);
+    fn print_metrics(&self, metrics: &Vec<Metric>, explanation: &mut Explanation) {
+        print!("{:<16}", "lists");
+        for metric in metrics.iter() {
+            print!("{:>16}", metric);
+        }
         println!();
-        println!("Running Day 1, Part 2");
-        part2(&input);
-        return;
+
+        for (a, b) in self.column_pairs() {
+            let pair = format!("list{} & list{}", a + 1, b + 1);
+            print!("{:<16}", pair);
+            for metric in metrics.iter() {
+                let value = self.metric(*metric, a, b);
+                print!("{:>16.4}", value);
+                explanation.step(format!("{} {}", pair, metric), format!("{:.4}", value));
+            }
+            println!();
+        }
+    }
+
+    fn quantile(&self, col: usize, q: f64) -> i64 {
+        // Nearest rank quantile, straight from the sorted counts.
+        let rank = ((q * self.num_rows as f64).ceil() as u64).max(1);
+        let mut seen: u64 = 0;
+        for (val, count) in self.counts[col].iter() {
+            seen += count;
+            if seen >= rank {
+                return *val;
+            }
+        }
+        return *self.counts[col].keys().last().unwrap();
     }
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 1, Part 1");
-            part1(&input)
+    fn print_summary(&self) {
+        println!("Summary of {} rows:", self.num_rows);
+        for col in 0..self.num_columns() {
+            let quantiles: Vec<String> = QUANTILES
+                .iter()
+                .map(|q| format!("p{:.0}={}", q * 100.0, self.quantile(col, *q)))
+                .collect();
+            println!(
+                "    list{}: min={} median={} max={} distinct={} [{}]",
+                col + 1,
+                self.counts[col].keys().next().unwrap(),
+                self.quantile(col, 0.5),
+                self.counts[col].keys().last().unwrap(),
+                self.counts[col].len(),
+                quantiles.join(", ")
+            );
         }
-        2 => {
-            println!("Running Day 1, Part 2");
-            part2(&input)
+
+        let mut pairs: Vec<((usize, usize), i64)> = self
+            .column_pairs()
+            .into_iter()
+            .map(|(a, b)| ((a, b), self.similarity(a, b)))
+            .collect();
+        pairs.sort_by(|x, y| y.1.cmp(&x.1));
+
+        println!("Most similar lists:");
+        for ((a, b), score) in pairs.iter().take(3) {
+            println!(
+                "    list{} & list{}: similarity={} distance={}",
+                a + 1,
+                b + 1,
+                score,
+                self.distance(*a, *b)
+            );
         }
-        _ => panic!("Invalid Part :("),
     }
 }
 
-fn part1(input_file: &PathBuf) {
-    let input_file = File::open(input_file).expect(
-        format!(
-            "Could not open input file: {}",
-            input_file.to_string_lossy()
-        )
-        .as_str(),
-    );
-    let lines = BufReader::new(input_file).lines();
-
-    let mut list1: Vec<i32> = Vec::new();
-    let mut list2: Vec<i32> = Vec::new();
-
-    for line in lines {
-        let line = line.expect("Could not read line");
-        let parts: Vec<&str> = line.split_ascii_whitespace().collect();
-        list1.push(parts[0].parse::<i32>().unwrap());
-        list2.push(parts[1].parse::<i32>().unwrap());
-    }
-
-    list1.sort();
-    list2.sort();
-    println!("list1: {:?}", list1);
-    println!("list2: {:?}", list2);
-
-    let diffs: Vec<i32> = list1
-        .into_iter()
-        .zip(list2.into_iter())
-        .map(|x| (x.0 - x.1).abs())
-        .collect();
-    println!("diffs: {:?}", diffs);
-    let sum: i32 = diffs.iter().sum();
-    println!("Sum: {}", sum);
+fn ranks(values: &Vec<i64>) -> Vec<f64> {
+    // Ranks starting at 1, ties share the average of the ranks they span.
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by_key(|i| values[*i]);
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+        let avg_rank = (start + end) as f64 / 2.0 + 1.0;
+        for idx in order[start..=end].iter() {
+            ranks[*idx] = avg_rank;
+        }
+        start = end + 1;
+    }
+    return ranks;
 }
 
-fn part2(input_file: &PathBuf) {
-    let input_file = File::open(input_file).expect(
-        format!(
-            "Could not open input file: {}",
-            input_file.to_string_lossy()
-        )
-        .as_str(),
-    );
-    let lines = BufReader::new(input_file).lines();
+fn spearman(list1: &Vec<i64>, list2: &Vec<i64>) -> f64 {
+    // Pearson correlation of the ranks, which handles ties correctly.
+    let ranks1 = ranks(list1);
+    let ranks2 = ranks(list2);
+    let n = ranks1.len() as f64;
+    let mean1 = ranks1.iter().sum::<f64>() / n;
+    let mean2 = ranks2.iter().sum::<f64>() / n;
 
-    let mut list1: Vec<u32> = Vec::new();
-    let mut freq_map: HashMap<u32, u32> = HashMap::new();
+    let mut covariance = 0.0;
+    let mut variance1 = 0.0;
+    let mut variance2 = 0.0;
+    for (r1, r2) in ranks1.iter().zip(ranks2.iter()) {
+        covariance += (r1 - mean1) * (r2 - mean2);
+        variance1 += (r1 - mean1).powi(2);
+        variance2 += (r2 - mean2).powi(2);
+    }
+    return covariance / (variance1 * variance2).sqrt();
+}
+
+fn kendall_tau_b(list1: &Vec<i64>, list2: &Vec<i64>) -> f64 {
+    // Knight's algorithm: with the rows sorted by list1 (then list2), every discordant pair is
+    // an inversion in list2, which a merge sort counts in O(n log n).
+    let mut rows: Vec<(i64, i64)> = list1.iter().copied().zip(list2.iter().copied()).collect();
+    rows.sort();
+
+    let n = rows.len() as i64;
+    let all_pairs = n * (n - 1) / 2;
+    let xs: Vec<i64> = rows.iter().map(|r| r.0).collect();
+    let ties1 = tied_pairs(&xs);
+    let ties_both = tied_pairs(&rows);
 
-    for line in lines {
-        let line = line.expect("Could not read line");
-        let parts: Vec<&str> = line.split_ascii_whitespace().collect();
-        list1.push(parts[0].parse::<u32>().unwrap());
+    let mut ys: Vec<i64> = rows.iter().map(|r| r.1).collect();
+    let discordant = count_inversions(&mut ys);
+    let ties2 = tied_pairs(&ys); // Sorted by now
 
-        let val2 = parts[1].parse::<u32>().unwrap();
-        let count = freq_map.entry(val2).or_insert(0);
-        (*count) += 1;
+    let numerator = all_pairs - ties1 - ties2 + ties_both - 2 * discordant;
+    let pairs1 = (all_pairs - ties1) as f64;
+    let pairs2 = (all_pairs - ties2) as f64;
+    return numerator as f64 / (pairs1 * pairs2).sqrt();
+}
+
+// Pairs of equal values in a sorted list.
+fn tied_pairs<T: PartialEq>(sorted: &[T]) -> i64 {
+    return sorted
+        .chunk_by(|a, b| a == b)
+        .map(|run| run.len() as i64 * (run.len() as i64 - 1) / 2)
+        .sum();
+}
+
+// Sorts values and returns how many pairs were out of order, equal values don't count.
+fn count_inversions(values: &mut Vec<i64>) -> i64 {
+    if values.len() < 2 {
+        return 0;
+    }
+    let mut right = values.split_off(values.len() / 2);
+    let mut inversions = count_inversions(values) + count_inversions(&mut right);
+
+    let mut merged: Vec<i64> = Vec::with_capacity(values.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+    while i < values.len() && j < right.len() {
+        if values[i] <= right[j] {
+            merged.push(values[i]);
+            i += 1;
+        } else {
+            merged.push(right[j]);
+            inversions += (values.len() - i) as i64;
+            j += 1;
+        }
     }
+    merged.extend_from_slice(&values[i..]);
+    merged.extend_from_slice(&right[j..]);
+    *values = merged;
+    return inversions;
+}
+
+fn part1(input_file: &PathBuf) -> Explanation {
+    let streaming: bool = config::day_param(1, "streaming", false);
+    let lists = LocationLists::from_file(input_file, streaming);
 
-    println!("{:?}", &list1);
-    println!("{:#?}", &freq_map);
+    let mut explanation = Explanation::default();
+    if !streaming {
+        // Sorted copies, the rank correlations need the columns in input order.
+        let mut sorted: Vec<Vec<i64>> = lists.columns.clone();
+        for (idx, list) in sorted.iter_mut().enumerate() {
+            list.sort();
+            println!("list{}: {:?}", idx + 1, list);
+        }
+
+        for (l1, l2) in sorted[0].iter().zip(sorted[1].iter()) {
+            explanation.step(format!("{} vs {}", l1, l2), (l1 - l2).abs());
+        }
+
+        let diffs: Vec<i64> = sorted[0]
+            .iter()
+            .zip(sorted[1].iter())
+            .map(|x| (x.0 - x.1).abs())
+            .collect();
+        println!("diffs: {:?}", diffs);
+    }
+
+    if lists.num_columns() > 2 {
+        for (a, b) in lists.column_pairs() {
+            let distance = lists.distance(a, b);
+            println!("Distance list{} & list{}: {}", a + 1, b + 1, distance);
+            explanation.step(format!("list{} & list{}", a + 1, b + 1), distance);
+        }
+    }
+
+    if config::day_param(1, "summary", false) {
+        lists.print_summary();
+    }
+
+    let metric: Option<String> = config::day_param(1, "metric", None);
+    if let Some(metric) = metric {
+        let metrics = Metric::parse_list(&metric);
+        if streaming && metrics.iter().any(|m| m.needs_rows()) {
+            panic!("Rank correlations need the row pairs, run without day1.streaming");
+        }
+        println!();
+        lists.print_metrics(&metrics, &mut explanation);
+        println!();
+    }
+
+    let sum = lists.distance(0, 1);
+    println!("Sum: {}", sum);
+    return explanation.answer(sum);
+}
+
+fn part2(input_file: &PathBuf) -> Explanation {
+    let streaming: bool = config::day_param(1, "streaming", false);
+    let lists = LocationLists::from_file(input_file, streaming);
+
+    let mut explanation = Explanation::default();
+    if !streaming {
+        println!("{:?}", &lists.columns[0]);
+        println!("{:#?}", &lists.counts[1]);
+
+        for val in lists.columns[0].iter() {
+            let freq = *lists.counts[1].get(val).unwrap_or(&0) as i64;
+            explanation.step(val, format!("{} x {} = {}", val, freq, val * freq));
+        }
+    }
+
+    if lists.num_columns() > 2 {
+        for (a, b) in lists.column_pairs() {
+            let similarity = lists.similarity(a, b);
+            println!("Similarity list{} & list{}: {}", a + 1, b + 1, similarity);
+            explanation.step(format!("list{} & list{}", a + 1, b + 1), similarity);
+        }
+    }
 
-    let mut sum: u32 = 0;
-    for val in list1 {
-        let freq = freq_map.get(&val).unwrap_or(&0);
-        sum += val * freq;
+    if config::day_param(1, "summary", false) {
+        lists.print_summary();
     }
 
+    let sum = lists.similarity(0, 1);
     println!("Sum: {}", sum);
+    return explanation.answer(sum);
 }
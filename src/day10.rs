@@ -8,30 +8,11 @@ use std::{
 
 use colored::Colorize;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day10.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 10, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 10, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 10, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 10, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(10));
+    runner::run_parts(10, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -121,6 +102,7 @@ impl State {
 
         while !active_nodes.is_empty() {
             let node = active_nodes.pop().unwrap();
+            metrics::incr("nodes_visited");
             self.trail_score[node] += 1;
             visited.insert(node);
 
@@ -192,6 +174,18 @@ impl State {
             .sum();
     }
 
+    fn explain_trailhead_scores(&self) -> Explanation {
+        let mut explanation = Explanation::default();
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, level) in row.iter().enumerate() {
+                if *level == 0 {
+                    explanation.step(format!("Trailhead at {:?}", (x, y)), self.trail_score[y][x]);
+                }
+            }
+        }
+        return explanation.answer(self.calculate_trailhead_scores());
+    }
+
     fn update_all_trail_scores_part2(&mut self) {
         let nines = self.get_all_9_positions();
         for pos in nines.iter() {
@@ -206,6 +200,7 @@ impl State {
         while !active_nodes.is_empty() {
             let mut next_active_nodes: HashMap<Point, u32> = HashMap::new();
             for (pos, score) in active_nodes.iter() {
+                metrics::incr("nodes_visited");
                 let curr_level = self.board[*pos];
                 self.trail_score[*pos] = *score;
                 let neighbors = self.get_neighbor_pos(*pos);
@@ -253,7 +248,7 @@ impl std::fmt::Display for State {
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let mut state = State::from_file(input_file);
     println!("{}", state);
 
@@ -267,9 +262,10 @@ fn part1(input_file: &PathBuf) {
             .green()
             .bold()
     );
+    return state.explain_trailhead_scores();
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let mut state = State::from_file(input_file);
     println!("{}", state);
 
@@ -283,4 +279,5 @@ fn part2(input_file: &PathBuf) {
             .green()
             .bold()
     );
+    return state.explain_trailhead_scores();
 }
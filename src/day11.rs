@@ -8,33 +8,14 @@ use std::{
 
 use colored::Colorize;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day11.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 11, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 11, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 11, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 11, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(11));
+    runner::run_parts(11, part_opt, || part1(&input), || part2(&input));
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let input_file = File::open(input_file).expect(
         format!(
             "Could not open input file: {}",
@@ -56,7 +37,7 @@ fn part1(input_file: &PathBuf) {
 
     println!("Initial: {:?}", stones);
 
-    let num_blinks = 25;
+    let num_blinks = config::day_param(11, "part1_blinks", 25);
 
     // Cache (initial_state -> (step_count -> [final_states]))
     let mut cache: HashMap<u64, HashMap<u64, Vec<u64>>> = HashMap::new();
@@ -71,6 +52,19 @@ fn part1(input_file: &PathBuf) {
         "Number of stones: {}",
         format!("{}", final_stones.len()).as_str().green().bold(),
     );
+
+    let mut explanation = Explanation::default();
+    for stone in stones.iter() {
+        explanation.step(
+            format!("Stone {}", stone),
+            format!(
+                "{} stones after {} blinks",
+                cache[stone][&num_blinks].len(),
+                num_blinks
+            ),
+        );
+    }
+    return explanation.answer(final_stones.len());
 }
 
 fn blink(
@@ -94,6 +88,7 @@ fn blink(
         });
 
         if stone_entry.contains_key(&num_blinks) {
+            metrics::incr("cache_hits");
             // println!(
             //     "Cache Hit! {:?} -> {:?} -> {:?}",
             //     stone,
@@ -105,6 +100,7 @@ fn blink(
         }
 
         // Cache miss. Find the last step calculated
+        metrics::incr("cache_misses");
         let mut last_step_calculated = *stone_entry
             .keys()
             .filter(|k| **k < num_blinks)
@@ -155,7 +151,7 @@ fn calculate_next_stones(stone: u64) -> Vec<u64> {
     return vec![stone * 2024];
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let input_file = File::open(input_file).expect(
         format!(
             "Could not open input file: {}",
@@ -175,7 +171,7 @@ fn part2(input_file: &PathBuf) {
         break;
     }
 
-    let num_blinks = 75;
+    let num_blinks = config::day_param(11, "part2_blinks", 75);
 
     let mut cache: HashMap<u64, HashMap<u64, u64>> = HashMap::new();
 
@@ -189,6 +185,19 @@ fn part2(input_file: &PathBuf) {
         "Number of stones: {}",
         total_stones.to_string().as_str().green().bold(),
     );
+
+    let mut explanation = Explanation::default();
+    for stone in stones.iter() {
+        explanation.step(
+            format!("Stone {}", stone),
+            format!(
+                "{} stones after {} blinks",
+                get_number_of_stones_after_n_blinks(*stone, &mut cache, num_blinks),
+                num_blinks
+            ),
+        );
+    }
+    return explanation.answer(total_stones);
 }
 
 fn get_number_of_stones_after_n_blinks(
@@ -201,9 +210,11 @@ fn get_number_of_stones_after_n_blinks(
     }
 
     if cache.contains_key(&stone) && cache.get(&stone).unwrap().contains_key(&(n as u64)) {
+        metrics::incr("cache_hits");
         return *cache.get(&stone).unwrap().get(&(n as u64)).unwrap();
     }
 
+    metrics::incr("cache_misses");
     let next_state = calculate_next_stones(stone);
     let total: u64 = next_state
         .iter()
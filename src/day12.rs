@@ -9,30 +9,11 @@ use std::{
 
 use colored::{Colorize, CustomColor};
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day12.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 12, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 12, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 12, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 12, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(12));
+    runner::run_parts(12, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -187,6 +168,7 @@ impl Garden {
             visited_coords.insert(coord);
 
             self.id_map[coord] = id;
+            metrics::incr("cells_labelled");
             area += 1;
 
             let group_neighbors = self.get_group_neighbors(&coord);
@@ -408,7 +390,7 @@ impl Display for Garden {
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let mut garden = Garden::from_file(input_file);
     println!("{}", garden);
 
@@ -423,9 +405,27 @@ fn part1(input_file: &PathBuf) {
             .green()
             .bold()
     );
+
+    let mut ids: Vec<&i32> = garden.id_to_perimeter_area.keys().collect();
+    ids.sort();
+
+    let mut explanation = Explanation::default();
+    for id in ids {
+        let (perimeter, area) = garden.id_to_perimeter_area[id];
+        explanation.step(
+            format!("Region {} ({})", id, garden.id_to_plant[id]),
+            format!(
+                "perimeter {} x area {} = {}",
+                perimeter,
+                area,
+                perimeter * area
+            ),
+        );
+    }
+    return explanation.answer(garden.total_perimeter_times_area());
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let mut garden = Garden::from_file(input_file);
     println!("{}", garden);
 
@@ -440,4 +440,18 @@ fn part2(input_file: &PathBuf) {
             .green()
             .bold()
     );
+
+    let mut ids: Vec<&i32> = garden.id_to_num_sides.keys().collect();
+    ids.sort();
+
+    let mut explanation = Explanation::default();
+    for id in ids {
+        let num_sides = garden.id_to_num_sides[id];
+        let area = garden.id_to_perimeter_area[id].1;
+        explanation.step(
+            format!("Region {} ({})", id, garden.id_to_plant[id]),
+            format!("sides {} x area {} = {}", num_sides, area, num_sides * area),
+        );
+    }
+    return explanation.answer(garden.total_num_sides_times_area());
 }
@@ -8,30 +8,11 @@ use std::{
 use colored::Colorize;
 use regex::Regex;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day13.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 13, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 13, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 13, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 13, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(13));
+    runner::run_parts(13, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -107,6 +88,7 @@ impl Problem {
     }
 
     fn solve(&self) -> Result<(i128, i128), ()> {
+        metrics::incr("machines_solved");
         // Okay this function is a little random as written, but
         // it basically encodes the mathematical solution to the Problem
         //
@@ -197,7 +179,7 @@ fn get_problems_from_file(input_file: &PathBuf, prize_prefix: i128) -> Vec<Probl
     return problems;
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let problems = get_problems_from_file(input_file, 0);
 
     let solutions: Vec<(i128, i128)> = problems
@@ -217,9 +199,10 @@ fn part1(input_file: &PathBuf) {
         "Tokens Spent: {}",
         tokens.to_string().as_str().green().bold()
     );
+    return explain_solutions(&problems, Some(100)).answer(tokens);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let problems = get_problems_from_file(input_file, 10000000000000);
 
     let solutions: Vec<(i128, i128)> = problems
@@ -238,4 +221,33 @@ fn part2(input_file: &PathBuf) {
         "Tokens Spent: {}",
         tokens.to_string().as_str().green().bold()
     );
+    return explain_solutions(&problems, None).answer(tokens);
+}
+
+fn explain_solutions(problems: &Vec<Problem>, max_presses: Option<i128>) -> Explanation {
+    let mut explanation = Explanation::default();
+    for (idx, problem) in problems.iter().enumerate() {
+        let subject = format!("Machine {}", idx + 1);
+        match problem.solve() {
+            Ok((n_a, n_b)) if max_presses.is_none_or(|m| n_a <= m && n_b <= m) => {
+                explanation.step(
+                    subject,
+                    format!(
+                        "A x {} + B x {} reaches ({}, {}) for {} tokens",
+                        n_a,
+                        n_b,
+                        problem.prize.x,
+                        problem.prize.y,
+                        n_a * 3 + n_b
+                    ),
+                );
+            }
+            Ok((n_a, n_b)) => explanation.step(
+                subject,
+                format!("needs A x {} + B x {}, too many presses", n_a, n_b),
+            ),
+            Err(_) => explanation.step(subject, "no whole number of presses reaches the prize"),
+        }
+    }
+    return explanation;
 }
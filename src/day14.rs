@@ -9,30 +9,11 @@ use std::{
 use colored::Colorize;
 use regex::Regex;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day14.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 14, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 14, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{cancel, config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 14, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 14, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(14));
+    runner::run_parts(14, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -122,21 +103,22 @@ impl Robot {
     }
 
     fn move_for_n_secs(&mut self, n_secs: i32, board_size: &(i32, i32)) {
+        metrics::incr("robot_moves");
         let new_pos = self.position + (self.velocity * n_secs);
         self.position = new_pos.wrap_to_size(board_size);
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let mut robots = create_robots_from_file(input_file);
     println!("# Robots: {:#?}", robots.len());
-    let board_size = (101, 103);
+    let board_size = config::day_param(14, "board_size", (101, 103));
 
     println!("Starting State:");
     pretty_print_robot_count(&robots, &board_size);
     println!();
 
-    let num_secs = 100;
+    let num_secs = config::day_param(14, "num_secs", 100);
     robots
         .iter_mut()
         .for_each(|r| r.move_for_n_secs(num_secs, &board_size));
@@ -154,6 +136,13 @@ fn part1(input_file: &PathBuf) {
         "Safety Factor: {}",
         safety_factor.to_string().as_str().green().bold()
     );
+
+    let mut explanation = Explanation::default();
+    explanation.step("Top left", num_robots.0);
+    explanation.step("Top right", num_robots.1);
+    explanation.step("Bottom left", num_robots.2);
+    explanation.step("Bottom right", num_robots.3);
+    return explanation.answer(safety_factor);
 }
 
 fn create_robots_from_file(input_file: &PathBuf) -> Vec<Robot> {
@@ -247,10 +236,10 @@ fn pretty_print_robot_count(robots: &Vec<Robot>, board_size: &(i32, i32)) {
     }
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let mut robots = create_robots_from_file(input_file);
     println!("# Robots: {:#?}", robots.len());
-    let board_size = (101, 103);
+    let board_size = config::day_param(14, "board_size", (101, 103));
 
     let mut user_input = String::new();
     let mut num_seconds = 0;
@@ -259,10 +248,16 @@ fn part2(input_file: &PathBuf) {
     pretty_print_robot_count(&robots, &board_size);
     println!();
 
-    let avg_threshold = 1.001;
-    loop {
+    let avg_threshold = config::day_param(14, "avg_threshold", 1.001);
+    let mut explanation = Explanation::default();
+    while !cancel::tick() {
         let avg_robots = get_average_count_per_cell(&robots);
+        metrics::incr("frames_checked");
         if avg_robots <= avg_threshold {
+            explanation.step(
+                format!("After {}s", num_seconds),
+                format!("average robots per occupied cell {}", avg_robots),
+            );
             println!("Board After {}s", num_seconds);
             pretty_print_robot_count(&robots, &board_size);
             println!();
@@ -279,4 +274,6 @@ fn part2(input_file: &PathBuf) {
             .iter_mut()
             .for_each(|r| r.move_for_n_secs(1, &board_size));
     }
+
+    return explanation.answer(num_seconds);
 }
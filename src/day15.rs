@@ -9,30 +9,11 @@ use std::{
 
 use colored::Colorize;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day15.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 15, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 15, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 15, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 15, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(15));
+    runner::run_parts(15, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -261,6 +242,7 @@ impl State {
         // Returns true if an instruction was processed. False otherwise
         if let Some(dir) = self.pending_instructions.pop_front() {
             self.move_robot_in_dir(&dir);
+            metrics::incr("instructions_processed");
             self.processed_instructions.push(dir);
             return true;
         }
@@ -396,6 +378,22 @@ impl State {
         return next_coord;
     }
 
+    fn explain_box_gps(&self) -> Explanation {
+        let mut explanation = Explanation::default();
+        explanation.step("Instructions processed", self.processed_instructions.len());
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, entity) in row.iter().enumerate() {
+                if entity == &Entity::Box || entity == &Entity::BoxLeft {
+                    explanation.step(
+                        format!("Box at {:?}", (x, y)),
+                        format!("100 x {} + {} = {}", y, x, (100 * y) + x),
+                    );
+                }
+            }
+        }
+        return explanation;
+    }
+
     fn sum_of_all_box_gps(&self) -> usize {
         self.board
             .iter()
@@ -442,7 +440,7 @@ impl Display for State {
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let mut state = State::from_file(input_file, false);
     println!("Initial State:");
     println!("{}", state);
@@ -472,9 +470,10 @@ fn part1(input_file: &PathBuf) {
         "Sum of GPS of all boxes: {}",
         gps_sum.to_string().as_str().green().bold()
     );
+    return state.explain_box_gps().answer(gps_sum);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let mut state = State::from_file(input_file, true);
     println!("Initial State:");
     println!("{}", state);
@@ -505,4 +504,5 @@ fn part2(input_file: &PathBuf) {
         "Sum of GPS of all boxes: {}",
         gps_sum.to_string().as_str().green().bold()
     );
+    return state.explain_box_gps().answer(gps_sum);
 }
@@ -11,30 +11,11 @@ use std::{
 use colored::Colorize;
 use priority_queue::PriorityQueue;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day16.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 16, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 16, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 16, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 16, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(16));
+    runner::run_parts(16, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Ord, PartialOrd)]
@@ -233,6 +214,7 @@ impl Maze {
 
         while !active_reindeers.is_empty() {
             let (curr_reindeer, cost) = active_reindeers.pop().unwrap();
+            metrics::incr("nodes_popped");
             let cost = cost.0;
             explored_reindeers.insert(curr_reindeer);
 
@@ -287,6 +269,7 @@ impl Maze {
 
         while !active_reindeers.is_empty() {
             let (curr_reindeer, cost) = active_reindeers.pop().unwrap();
+            metrics::incr("nodes_popped");
             let cost = cost.0;
             if least_cost.is_some() && least_cost.unwrap() < cost {
                 // exit early if the cost is greater than the path of least cost
@@ -493,17 +476,28 @@ impl Display for Maze {
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let maze = Maze::from_file(input_file);
     println!("{}", maze);
 
     let (path, cost) = maze.find_path_of_least_cost();
 
+    // Turns happen in place, so every step that doesn't change the position is a turn.
+    let num_turns = path.windows(2).filter(|w| w[0].pos == w[1].pos).count();
+    let num_moves = path.len() - 1 - num_turns;
+    let mut explanation = Explanation::default();
+    explanation.step("Moves", format!("{} x 1 = {}", num_moves, num_moves));
+    explanation.step(
+        "Turns",
+        format!("{} x 1000 = {}", num_turns, num_turns * 1000),
+    );
+
     maze.pretty_print_path(path);
     println!("Cost of path: {}", cost.to_string().as_str().green().bold());
+    return explanation.answer(cost);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let maze = Maze::from_file(input_file);
     println!("{}", maze);
 
@@ -513,4 +507,10 @@ fn part2(input_file: &PathBuf) {
         "Number of seats on path: {}",
         num_seats.to_string().as_str().green().bold()
     );
+
+    let mut explanation = Explanation::default();
+    let num_forks = all_paths.values().filter(|prev| prev.len() > 1).count();
+    explanation.step("States on a least cost path", all_paths.len());
+    explanation.step("States reachable from more than one best state", num_forks);
+    return explanation.answer(num_seats);
 }
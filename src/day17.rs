@@ -9,30 +9,11 @@ use colored::Colorize;
 use itertools::Itertools;
 use regex::Regex;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day17.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 17, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 17, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 17, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 17, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(17));
+    runner::run_parts(17, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone)]
@@ -132,6 +113,7 @@ impl Computer {
 
         let opcode = self.program[self.instr_ptr];
         let operand = self.program[self.instr_ptr + 1];
+        metrics::incr("instructions_executed");
         self.instr_ptr += 2;
 
         let output = match opcode {
@@ -254,7 +236,7 @@ impl Computer {
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let mut computer = Computer::from_file(input_file);
     println!("Program: {:?}", computer.program);
 
@@ -273,9 +255,20 @@ fn part1(input_file: &PathBuf) {
     let t: String =
         Itertools::intersperse(outputs.iter().map(|n| n.to_string()), ",".to_string()).collect();
     println!("Output: {}", t.green().bold());
+
+    let mut explanation = Explanation::default();
+    explanation.step("Program", format!("{:?}", computer.program));
+    explanation.step(
+        "Final registers",
+        format!(
+            "A = {}, B = {}, C = {}",
+            computer.reg_a, computer.reg_b, computer.reg_c
+        ),
+    );
+    return explanation.answer(t);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let computer = Computer::from_file(input_file);
 
     let target_output: Vec<u8> = vec![2, 4, 1, 5, 7, 5, 1, 6, 4, 3, 5, 5, 0, 3, 3, 0];
@@ -283,6 +276,7 @@ fn part2(input_file: &PathBuf) {
     let mut active_inputs: HashSet<i64> = HashSet::new();
     active_inputs.insert(0);
     let mut found_len = 0;
+    let mut explanation = Explanation::default();
 
     for l in 0..target_output.len() {
         let curr_target = &target_output[(target_output.len() - l - 1)..target_output.len()];
@@ -307,6 +301,10 @@ fn part2(input_file: &PathBuf) {
             }
         }
         println!("Working Inputs: {:#?}", next_inputs);
+        explanation.step(
+            format!("Last {} outputs {:?}", l + 1, curr_target),
+            format!("{} candidate values of reg_a", next_inputs.len()),
+        );
         active_inputs = next_inputs;
     }
 
@@ -319,6 +317,7 @@ fn part2(input_file: &PathBuf) {
         min_solution.to_string().green().bold(),
         min_solution
     );
+    return explanation.answer(min_solution);
 }
 
 fn collect_output(computer: &mut Computer) -> Vec<u8> {
@@ -8,30 +8,11 @@ use std::{
 
 use colored::Colorize;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day18.txt"));
+use crate::{config, explain::Explanation, metrics, runner};
 
-    if part_opt.is_none() {
-        println!("Running Day 18, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 18, Part 2");
-        part2(&input);
-        return;
-    }
-
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 18, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 18, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(18));
+    runner::run_parts(18, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -150,6 +131,7 @@ impl Memory {
     }
 
     fn get_shortest_path_to_exit(&self) -> Option<Vec<Coord>> {
+        metrics::incr("path_searches");
         // Use a breadth-first search (BFS) to find the shortest path
         let mut prev_nodes: HashMap<Coord, Option<Coord>> = HashMap::new();
         let mut frontier: VecDeque<Coord> = VecDeque::new();
@@ -159,6 +141,7 @@ impl Memory {
 
         while !frontier.is_empty() {
             let current_coord = frontier.pop_front().unwrap();
+            metrics::incr("nodes_visited");
             // println!("{:?}", current_coord);
 
             if current_coord == self.end_pos {
@@ -254,11 +237,9 @@ impl Memory {
     }
 }
 
-fn part1(input_file: &PathBuf) {
-    // let board_size = (7 as usize, 7 as usize);
-    let board_size = (71 as usize, 71 as usize);
-    // let bytes_to_take = 12 as usize;
-    let bytes_to_take = 1024 as usize;
+fn part1(input_file: &PathBuf) -> Explanation {
+    let board_size: (usize, usize) = config::day_param(18, "board_size", (71, 71));
+    let bytes_to_take: usize = config::day_param(18, "bytes_to_take", 1024);
 
     let memory = Memory::from_file(input_file, board_size, Some(bytes_to_take));
     memory.pretty_print(None);
@@ -275,15 +256,24 @@ fn part1(input_file: &PathBuf) {
         "Shortest Path Length: {} steps",
         (path.len() - 1).to_string().green().bold()
     );
+
+    let mut explanation = Explanation::default();
+    explanation.step("Board size", format!("{:?}", board_size));
+    explanation.step("Corrupted bytes", bytes_to_take);
+    explanation.step(
+        "Path",
+        format!("{:?} to {:?}", path.first().unwrap(), path.last().unwrap()),
+    );
+    return explanation.answer(path.len() - 1);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let corruption_list = coord_list_from_file(input_file);
-    // let board_size = (7 as usize, 7 as usize);
-    let board_size = (71 as usize, 71 as usize);
+    let board_size: (usize, usize) = config::day_param(18, "board_size", (71, 71));
 
     let mut left_limit = 0;
     let mut right_limit = corruption_list.len() - 1;
+    let mut explanation = Explanation::default();
 
     while left_limit < right_limit {
         let mid_point = (left_limit + right_limit) / 2;
@@ -297,9 +287,11 @@ fn part2(input_file: &PathBuf) {
         match path {
             Some(_) => {
                 left_limit = mid_point + 1;
+                explanation.step(format!("First {} bytes", mid_point + 1), "exit reachable");
             }
             None => {
                 right_limit = mid_point;
+                explanation.step(format!("First {} bytes", mid_point + 1), "exit blocked");
             }
         }
     }
@@ -329,13 +321,12 @@ fn part2(input_file: &PathBuf) {
     memory.pretty_print(None);
 
     let first_blocked_coord = corruption_list[first_blocked_solution];
+    let answer = format!("{},{}", first_blocked_coord.x, first_blocked_coord.y);
     println!(
         "First infeasible memory: {}",
-        format!("{},{}", first_blocked_coord.x, first_blocked_coord.y)
-            .as_str()
-            .green()
-            .bold()
+        answer.as_str().green().bold()
     );
+    return explanation.answer(answer);
 }
 
 fn coord_list_from_file(input_file: &PathBuf) -> Vec<Coord> {
@@ -8,30 +8,11 @@ use std::{
 use colored::Colorize;
 use priority_queue::PriorityQueue;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day19.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 19, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 19, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 19, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 19, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(19));
+    runner::run_parts(19, part_opt, || part1(&input), || part2(&input));
 }
 
 fn read_towel_and_pattern_from_file(input_file: &PathBuf) -> (Vec<String>, Vec<String>) {
@@ -61,18 +42,21 @@ fn read_towel_and_pattern_from_file(input_file: &PathBuf) -> (Vec<String>, Vec<S
     return (towels, pattern);
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let (towels, patterns) = read_towel_and_pattern_from_file(input_file);
     println!("Towels: {:?}", towels);
     // println!("Patterns: {:#?}", patterns);
 
+    let mut explanation = Explanation::default();
     let mut num_possible_patterns: usize = 0;
     for pattern in patterns {
         if can_make_pattern(&towels, &pattern) {
             println!("{}  {}", "✓".green().bold(), pattern);
             num_possible_patterns += 1;
+            explanation.step(pattern, "possible");
         } else {
             println!("{}  {}", "✗".red().bold(), pattern);
+            explanation.step(pattern, "impossible");
         }
     }
 
@@ -81,6 +65,7 @@ fn part1(input_file: &PathBuf) {
         "Number of possible patterns: {}",
         num_possible_patterns.to_string().green().bold()
     );
+    return explanation.answer(num_possible_patterns);
 }
 
 fn can_make_pattern(towels: &[String], pattern: &String) -> bool {
@@ -90,6 +75,7 @@ fn can_make_pattern(towels: &[String], pattern: &String) -> bool {
 
     while !pattern_to_make.is_empty() {
         let current_pattern = pattern_to_make.pop().unwrap();
+        metrics::incr("patterns_popped");
         // println!("Current pattern to make: {:?}", current_pattern);
         if current_pattern.is_empty() {
             return true;
@@ -106,11 +92,12 @@ fn can_make_pattern(towels: &[String], pattern: &String) -> bool {
     return false;
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let (towels, patterns) = read_towel_and_pattern_from_file(input_file);
     println!("Towels: {:?}", towels);
     // println!("Patterns: {:#?}", patterns);
 
+    let mut explanation = Explanation::default();
     let mut total_number_of_ways_to_make_pattern = 0;
     for pattern in patterns {
         // println!("Current pattern to make: {:?}", pattern);
@@ -126,6 +113,7 @@ fn part2(input_file: &PathBuf) {
             },
             pattern
         );
+        explanation.step(pattern, format!("{} ways", num_ways));
     }
 
     println!();
@@ -135,7 +123,8 @@ fn part2(input_file: &PathBuf) {
             .to_string()
             .green()
             .bold()
-    )
+    );
+    return explanation.answer(total_number_of_ways_to_make_pattern);
 }
 
 fn count_number_of_ways_to_make_pattern(towels: &[String], pattern: &String) -> usize {
@@ -147,6 +136,7 @@ fn count_number_of_ways_to_make_pattern(towels: &[String], pattern: &String) ->
 
     while !frontier.is_empty() {
         let (curr_pattern, _) = frontier.pop().unwrap();
+        metrics::incr("frontier_pops");
         let curr_count = counts_map[curr_pattern];
         // println!("Current Pattern: {:?}", curr_pattern);
         // println!("Current Count: {}", curr_count);
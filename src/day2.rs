@@ -2,33 +2,168 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 
+use colored::Colorize;
+use itertools::Itertools;
+
+use crate::{config, explain::Explanation, metrics, runner};
+
 pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day2.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 2, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 2, Part 2");
-        part2(&input);
-        return;
+    let input = input_opt.unwrap_or(config::default_input(2));
+    runner::run_parts(2, part_opt, || part1(&input), || part2(&input));
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SafetyRules {
+    min_step: i32,
+    max_step: i32,
+    strict: bool,        // Non-strict reports may also repeat a level
+    max_removals: usize, // Levels that may be dropped to make a report safe
+}
+
+impl SafetyRules {
+    fn from_config(max_removals: usize) -> Self {
+        return SafetyRules {
+            min_step: config::day_param(2, "min_step", 1),
+            max_step: config::day_param(2, "max_step", 3),
+            strict: config::day_param(2, "strict", true),
+            max_removals,
+        };
     }
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 2, Part 1");
-            part1(&input)
+    fn is_valid_step(&self, from: i32, to: i32, order: i32) -> bool {
+        let diff = to - from;
+        if diff == 0 {
+            return !self.strict;
+        }
+        return diff.signum() == order
+            && diff.abs() >= self.min_step
+            && diff.abs() <= self.max_step;
+    }
+
+    // Returns the smallest set of indices that have to be dropped for the report to be safe, or
+    // None if that needs more than max_removals levels.
+    fn levels_to_remove(&self, report: &Vec<i32>) -> Option<Vec<usize>> {
+        if report.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let increasing = self.levels_to_remove_with_order(report, 1);
+        let decreasing = self.levels_to_remove_with_order(report, -1);
+        return match (increasing, decreasing) {
+            (Some(inc), Some(dec)) => Some(if dec.len() < inc.len() { dec } else { inc }),
+            (inc, dec) => inc.or(dec),
+        };
+    }
+
+    fn levels_to_remove_with_order(&self, report: &Vec<i32>, order: i32) -> Option<Vec<usize>> {
+        // removals[i] is the fewest levels dropped before i when i is the last level kept so far.
+        // Only the last kept level matters for what can follow, so keeping the fewest removals for
+        // each i is enough, and each level only looks back at most max_removals + 1 levels.
+        let k = self.max_removals;
+        let mut removals: Vec<Option<usize>> = vec![None; report.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; report.len()];
+        let mut transitions: u64 = 0;
+
+        for i in 0..report.len() {
+            if i <= k {
+                removals[i] = Some(i); // Drop everything before i
+            }
+            for prev in i.saturating_sub(k + 1)..i {
+                transitions += 1;
+                let Some(prev_removals) = removals[prev] else {
+                    continue;
+                };
+                let total = prev_removals + (i - prev - 1);
+                if total > k || !self.is_valid_step(report[prev], report[i], order) {
+                    continue;
+                }
+                if removals[i].is_none_or(|best| total < best) {
+                    removals[i] = Some(total);
+                    previous[i] = Some(prev);
+                }
+            }
+        }
+        metrics::add("dp_transitions", transitions);
+
+        // Pick the best last kept level, everything after it is dropped.
+        let (mut last, _) = (report.len().saturating_sub(k + 1)..report.len())
+            .filter_map(|i| removals[i].map(|r| (i, r + report.len() - 1 - i)))
+            .filter(|(_, total)| *total <= k)
+            .min_by_key(|(_, total)| *total)?;
+
+        let mut kept = vec![false; report.len()];
+        kept[last] = true;
+        while let Some(prev) = previous[last] {
+            kept[prev] = true;
+            last = prev;
         }
-        2 => {
-            println!("Running Day 2, Part 2");
-            part2(&input)
+        return Some((0..report.len()).filter(|i| !kept[*i]).collect());
+    }
+
+    fn is_safe(&self, report: &Vec<i32>) -> bool {
+        return [1, -1].iter().any(|order| {
+            report
+                .windows(2)
+                .all(|w| self.is_valid_step(w[0], w[1], *order))
+        });
+    }
+
+    // Tries every combination of removed levels, smallest first. Returns all the combinations of
+    // the smallest size that make the report safe.
+    fn all_levels_to_remove(&self, report: &Vec<i32>) -> Vec<Vec<usize>> {
+        for num_removed in 0..=self.max_removals.min(report.len()) {
+            let valid: Vec<Vec<usize>> = (0..report.len())
+                .combinations(num_removed)
+                .filter(|removed| {
+                    metrics::incr("naive_checks");
+                    let remaining: Vec<i32> = (0..report.len())
+                        .filter(|i| !removed.contains(i))
+                        .map(|i| report[i])
+                        .collect();
+                    self.is_safe(&remaining)
+                })
+                .collect();
+            if !valid.is_empty() {
+                return valid;
+            }
         }
-        _ => panic!("Invalid Part :("),
+        return Vec::new();
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn describe_removal(report: &Vec<i32>, removed: &Vec<usize>) -> String {
+    return removed
+        .iter()
+        .map(|i| format!("index {} ({})", i, report[*i]))
+        .join(" + ");
+}
+
+fn highlight_removal(report: &Vec<i32>, removed: &Vec<usize>) -> String {
+    let levels = report
+        .iter()
+        .enumerate()
+        .map(|(i, level)| {
+            if removed.contains(&i) {
+                level.to_string().red().strikethrough().to_string()
+            } else {
+                level.to_string()
+            }
+        })
+        .join(", ");
+    return format!("[{}]", levels);
+}
+
+fn read_report(line: &str) -> Vec<i32> {
+    return line
+        .split_ascii_whitespace()
+        .map(|s| {
+            s.parse::<i32>()
+                .expect(format!("Failed to convert {} to i32", s).as_str())
+        })
+        .collect();
+}
+
+fn part1(input_file: &PathBuf) -> Explanation {
     let input_file = File::open(input_file).expect(
         format!(
             "Could not open input file: {}",
@@ -38,49 +173,29 @@ fn part1(input_file: &PathBuf) {
     );
     let lines = BufReader::new(input_file).lines();
 
+    let rules = SafetyRules::from_config(0);
+    let mut explanation = Explanation::default();
     let mut ordered_reports = 0;
     for line_res in lines {
         let line = line_res.expect("Could not read line");
-        let report: Vec<i32> = line
-            .split_ascii_whitespace()
-            .map(|s| {
-                s.parse::<i32>()
-                    .expect(format!("Failed to convert {} to u32", s).as_str())
-            })
-            .collect();
+        let report = read_report(&line);
 
         print!("Report: {:?}", &report);
-        if is_report_ordered_within_margin(&report) {
+        metrics::incr("reports_checked");
+        if rules.levels_to_remove(&report).is_some() {
             ordered_reports += 1;
             println!(": safe");
+            explanation.step(format!("{:?}", report), "safe");
         } else {
             println!(": unsafe");
+            explanation.step(format!("{:?}", report), "unsafe");
         }
     }
     println!("Ordered reports: {}", ordered_reports);
+    return explanation.answer(ordered_reports);
 }
 
-fn is_report_ordered_within_margin(report: &Vec<i32>) -> bool {
-    if report.len() < 2 {
-        return true;
-    }
-
-    let order = (report[1] - report[0]).signum();
-    for i in 1..report.len() {
-        let diff = report[i] - report[i - 1];
-        if diff.signum() != order {
-            return false;
-        }
-
-        let diff: i32 = diff.abs();
-        if diff < 1 || diff > 3 {
-            return false;
-        }
-    }
-    true
-}
-
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let input_file = File::open(input_file).expect(
         format!(
             "Could not open input file: {}",
@@ -90,122 +205,75 @@ fn part2(input_file: &PathBuf) {
     );
     let lines = BufReader::new(input_file).lines();
 
+    let rules = SafetyRules::from_config(config::day_param(2, "max_removals", 1));
+    let show_all_removals = config::day_param(2, "all_removals", false);
+    let cross_check = config::day_param(2, "cross_check", false);
+    let mut explanation = Explanation::default();
     let mut safe_reports = 0;
     for line_res in lines {
         let line = line_res.expect("Could not read line");
-        let report: Vec<i32> = line
-            .split_ascii_whitespace()
-            .map(|s| {
-                s.parse::<i32>()
-                    .expect(format!("Failed to convert {} to u32", s).as_str())
-            })
-            .collect();
+        let report = read_report(&line);
 
-        print!("Report: {:?}", &report);
-        if is_report_ordered_within_margin(&report) {
-            safe_reports += 1;
-            println!(": safe");
-            continue;
-        }
+        metrics::incr("reports_checked");
+        let report_str = format!("{:?}", report);
+        let removal = rules.levels_to_remove(&report);
 
-        let (negative_count, zero_count, positive_count) = get_report_order_counts(&report);
-        if zero_count > 1 {
-            println!(": unsafe -- too many repeated elements");
-            continue;
-        } else if negative_count > 1 && positive_count > 1 {
-            println!(": unsafe -- order can't be fixed");
-            continue;
+        let all_removals = if show_all_removals || cross_check {
+            rules.all_levels_to_remove(&report)
+        } else {
+            Vec::new()
+        };
+        if cross_check {
+            // The naive fixer finds every smallest removal, the fast one has to be one of them.
+            let agrees = match &removal {
+                Some(removed) => all_removals.contains(removed),
+                None => all_removals.is_empty(),
+            };
+            if !agrees {
+                panic!(
+                    "Fixer disagrees with naive check for {}: {:?} vs {:?}",
+                    report_str, removal, all_removals
+                );
+            }
         }
 
-        if report.len() == 3 {
-            if check_if_report_can_be_fixed(&report, -1) || check_if_report_can_be_fixed(&report, 1)
-            {
+        match removal {
+            Some(removed) if removed.is_empty() => {
                 safe_reports += 1;
-                println!(": safe -- fixable");
-            } else {
-                println!(": unsafe -- not-fixable");
+                println!("Report: {}: safe", report_str);
+                explanation.step(report_str, "safe");
+            }
+            Some(removed) => {
+                safe_reports += 1;
+                print!(
+                    "Report: {}: safe -- fixable by dropping {}",
+                    highlight_removal(&report, &removed),
+                    describe_removal(&report, &removed)
+                );
+                if show_all_removals {
+                    print!(
+                        " -- valid removals: {}",
+                        all_removals
+                            .iter()
+                            .map(|r| describe_removal(&report, r))
+                            .join(", ")
+                    );
+                }
+                println!();
+                explanation.step(
+                    report_str,
+                    format!(
+                        "safe after dropping {}",
+                        describe_removal(&report, &removed)
+                    ),
+                );
+            }
+            None => {
+                println!("Report: {}: unsafe -- not-fixable", report_str);
+                explanation.step(report_str, "unsafe, not fixable");
             }
-            continue;
-        }
-
-        let order = (positive_count - negative_count).signum();
-
-        if check_if_report_can_be_fixed(&report, order) {
-            safe_reports += 1;
-            println!(": safe -- fixable");
-        } else {
-            println!(": unsafe -- not-fixable");
         }
     }
     println!("Safe Report: {}", safe_reports);
-}
-
-fn check_if_report_can_be_fixed(report: &Vec<i32>, order: i32) -> bool {
-    if report.len() < 3 {
-        return true; // Short reports are always safe.
-    }
-
-    let mut error_count = 0;
-    let mut cursor = 0;
-    for i in 1..report.len() {
-        if error_count > 1 {
-            break; // Too many errors, don't bother fixing.
-        }
-
-        let diff = report[i] - report[cursor];
-        if diff.signum() != order {
-            error_count += 1;
-            continue;
-        }
-
-        let diff = diff.abs();
-        if diff < 1 || diff > 3 {
-            error_count += 1;
-            continue;
-        }
-        cursor = i; // Move the pointer to the last checked element.
-    }
-
-    if error_count < 2 {
-        return true; // Either no errors, or one error we can skip over
-    }
-
-    // There were more than 1 errors. Maybe skipping the first element fixes it?
-    let mut error_count = 1; // Can't fix any more things.
-    let mut cursor = 1; // skip the first element
-    for i in 2..report.len() {
-        if error_count > 1 {
-            break; // Too many errors, don't bother fixing.
-        }
-
-        let diff = report[i] - report[cursor];
-        if diff.signum() != order {
-            error_count += 1;
-            continue;
-        }
-
-        let diff = diff.abs();
-        if diff < 1 || diff > 3 {
-            error_count += 1;
-            continue;
-        }
-        cursor = i; // Move the pointer to the last checked element.
-    }
-    return error_count < 2;
-}
-
-fn get_report_order_counts(report: &Vec<i32>) -> (i32, i32, i32) {
-    let mut order_positive_count = 0;
-    let mut order_netagive_count = 0;
-    let mut order_zero_count = 0;
-    for i in 1..report.len() {
-        let order = (report[i] - report[i - 1]).signum();
-        match order {
-            -1 => order_netagive_count += 1,
-            0 => order_zero_count += 1,
-            1 => order_positive_count += 1,
-            _ => unreachable!(),
-        }
-    }
-    return (order_netagive_count, order_zero_count, order_positive_count);
+    return explanation.answer(safe_reports);
 }
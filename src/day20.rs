@@ -10,30 +10,11 @@ use std::{
 
 use colored::Colorize;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day20.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 20, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 20, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 20, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 20, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(20));
+    runner::run_parts(20, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -269,6 +250,7 @@ impl State {
             path.iter().enumerate().map(|(i, c)| (*c, i)).collect();
 
         for (curr_time, coord) in path.iter().enumerate() {
+            metrics::incr("cheat_origins_checked");
             self.get_cheatsy_neighbors(coord, cheat_step)
                 .into_iter()
                 .filter(|c| coord_to_time.contains_key(c))
@@ -283,12 +265,14 @@ impl State {
 
     fn get_cheat_counts_2(&self, path: &[Coord], cheat_step: usize) -> HashMap<usize, usize> {
         let mut savings_count: HashMap<usize, usize> = HashMap::new();
+        let mut cheats_checked: u64 = 0;
 
         for i in 0..path.len() - cheat_step - 1 {
             for j in i + cheat_step..path.len() {
                 let coord1 = path[i];
                 let coord2 = path[j];
                 let distance = coord1.manhattan_distance(&coord2);
+                cheats_checked += 1;
                 if distance > cheat_step {
                     continue;
                 }
@@ -300,6 +284,7 @@ impl State {
             }
         }
 
+        metrics::add("cheats_checked", cheats_checked);
         return savings_count;
     }
 
@@ -331,14 +316,15 @@ impl State {
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let state = State::from_file(input_file);
     state.pretty_print();
 
     let path = state.get_path();
     println!("Path length: {}", path.len());
 
-    let savings_count = state.get_cheat_counts(&path, 2);
+    let cheat_step = config::day_param(20, "part1_cheat_step", 2);
+    let savings_count = state.get_cheat_counts(&path, cheat_step);
 
     // let mut savings: Vec<(usize, usize)> = savings_count.iter().map(|(k, v)| (*k, *v)).collect();
     // savings.sort_by(|(a, _), (b, _)| a.cmp(b));
@@ -348,7 +334,7 @@ fn part1(input_file: &PathBuf) {
     //     println!("    {:>3} -> {}", k, v);
     // });
 
-    let optimal_saving_threshold: usize = 100;
+    let optimal_saving_threshold: usize = config::day_param(20, "saving_threshold", 100);
 
     let optimal_savings_count: usize = savings_count
         .iter()
@@ -362,18 +348,19 @@ fn part1(input_file: &PathBuf) {
         optimal_saving_threshold.to_string().yellow(),
         optimal_savings_count.to_string().green().bold()
     );
+    return explain_savings(&savings_count, optimal_saving_threshold).answer(optimal_savings_count);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let state = State::from_file(input_file);
     state.pretty_print();
 
     let path = state.get_path();
     println!("Path length: {}", path.len());
 
-    let cheat_step = 20;
+    let cheat_step = config::day_param(20, "part2_cheat_step", 20);
     let savings_count = state.get_cheat_counts_2(&path, cheat_step);
-    let optimal_saving_threshold: usize = 100;
+    let optimal_saving_threshold: usize = config::day_param(20, "saving_threshold", 100);
 
     // let mut savings: Vec<(usize, usize)> = savings_count
     //     .iter()
@@ -399,4 +386,19 @@ fn part2(input_file: &PathBuf) {
         optimal_saving_threshold.to_string().yellow(),
         optimal_savings_count.to_string().green().bold()
     );
+    return explain_savings(&savings_count, optimal_saving_threshold).answer(optimal_savings_count);
+}
+
+fn explain_savings(savings_count: &HashMap<usize, usize>, threshold: usize) -> Explanation {
+    let mut savings: Vec<(&usize, &usize)> = savings_count
+        .iter()
+        .filter(|(saving, _)| **saving >= threshold)
+        .collect();
+    savings.sort();
+
+    let mut explanation = Explanation::default();
+    for (saving, count) in savings {
+        explanation.step(format!("Saves {}ps", saving), format!("{} cheats", count));
+    }
+    return explanation;
 }
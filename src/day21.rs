@@ -10,30 +10,11 @@ use std::{
 use colored::Colorize;
 use regex::Regex;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day21.txt"));
+use crate::{config, explain::Explanation, metrics, runner};
 
-    if part_opt.is_none() {
-        println!("Running Day 21, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 21, Part 2");
-        part2(&input);
-        return;
-    }
-
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 21, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 21, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(21));
+    runner::run_parts(21, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Hash, PartialEq, PartialOrd, Ord, Eq, Clone, Copy)]
@@ -405,10 +386,11 @@ fn read_code_from_file(input_file: &PathBuf) -> Vec<(Vec<NumberPadKey>, usize)>
     return codes;
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let codes = read_code_from_file(input_file);
     // println!("Codes: {:?}", codes);
 
+    let mut explanation = Explanation::default();
     let mut total_complexity: usize = 0;
     for (code, value) in codes {
         print!("Code: ");
@@ -429,6 +411,10 @@ fn part1(input_file: &PathBuf) {
         );
 
         total_complexity += complexity;
+        explanation.step(
+            format!("Code {:03}A", value),
+            format!("{} x {} = {}", value, directions.len(), complexity),
+        );
         println!();
     }
 
@@ -436,6 +422,7 @@ fn part1(input_file: &PathBuf) {
         "Total Complexity: {}",
         total_complexity.to_string().green().bold()
     );
+    return explanation.answer(total_complexity);
 }
 
 fn get_shortest_directions_for_code(code: &[NumberPadKey]) -> Vec<DirectionPadKey> {
@@ -471,6 +458,7 @@ fn get_shortest_d_pad_directions(code: &[DirectionPadKey], level: usize) -> Vec<
 
     for key in code {
         let options = d_pad.move_hand_to(key);
+        metrics::add("d_pad_options", options.len() as u64);
 
         let min_option = options
             .iter()
@@ -484,6 +472,6 @@ fn get_shortest_d_pad_directions(code: &[DirectionPadKey], level: usize) -> Vec<
     return curr_best_min;
 }
 
-fn part2(_input_file: &PathBuf) {
+fn part2(_input_file: &PathBuf) -> Explanation {
     todo!("Implement Part2")
 }
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{HashMap, VecDeque},
     fs::File,
     io::{BufRead, BufReader},
     path::PathBuf,
@@ -7,30 +7,11 @@ use std::{
 
 use colored::Colorize;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day22.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 22, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 22, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{cancel, config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 22, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 22, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(22));
+    runner::run_parts(22, part_opt, || part1(&input), || part2(&input));
 }
 
 fn get_initial_numbers_from_file(input_file: &PathBuf) -> Vec<u64> {
@@ -80,23 +61,28 @@ fn next_secret_3(secret: u64) -> u64 {
 }
 
 fn next_secret(secret: u64) -> u64 {
+    metrics::incr("secrets_generated");
     let result = next_secret_1(secret);
     let result = next_secret_2(result);
     let result = next_secret_3(result);
     return result;
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let first_secrets = get_initial_numbers_from_file(input_file);
     // println!("{:#?}", first_secrets);
 
+    let num_secrets: usize = config::day_param(22, "num_secrets", 2000);
+
+    let mut explanation = Explanation::default();
     let mut secret_sum: u64 = 0;
     for secret in first_secrets {
         let mut working_secret = secret;
-        for i in 1..=2000 {
+        for i in 1..=num_secrets {
             let next_secret = next_secret(working_secret);
-            if i == 2000 {
+            if i == num_secrets {
                 println!("{:>10} : {}", secret, next_secret);
+                explanation.step(secret, next_secret);
             }
             working_secret = next_secret;
         }
@@ -105,53 +91,71 @@ fn part1(input_file: &PathBuf) {
 
     println!();
     println!("Sum of secrets: {}", secret_sum.to_string().green().bold());
+    return explanation.answer(secret_sum);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let first_secrets = get_initial_numbers_from_file(input_file);
 
-    // Brute force FTW!
-    let seq_maps: Vec<HashMap<(i64, i64, i64, i64), u64>> = first_secrets
-        .iter()
-        .map(|s| get_seq_price_map(*s))
-        .collect();
-
-    let seq_set: HashSet<(i64, i64, i64, i64)> =
-        seq_maps.iter().flat_map(|m| m.keys()).map(|k| *k).collect();
+    let num_secrets: usize = config::day_param(22, "num_secrets", 2000);
+
+    // Brute force FTW! The sums are kept up to date buyer by buyer, so when cancelled the best
+    // sequence is still found for the buyers seen so far.
+    let mut seq_maps: Vec<HashMap<(i64, i64, i64, i64), u64>> = Vec::new();
+    let mut seq_sums: HashMap<(i64, i64, i64, i64), u64> = HashMap::new();
+    for secret in first_secrets.iter() {
+        if cancel::tick() {
+            println!(
+                "Cancelled after {} of {} buyers",
+                seq_maps.len(),
+                first_secrets.len()
+            );
+            break;
+        }
+        let seq_map = get_seq_price_map(*secret, num_secrets);
+        for (seq, price) in seq_map.iter() {
+            *seq_sums.entry(*seq).or_insert(0) += price;
+        }
+        seq_maps.push(seq_map);
+    }
 
-    println!("Number of unique sequences: {}", seq_set.len());
+    println!("Number of unique sequences: {}", seq_sums.len());
 
-    let max_sum = seq_set
+    // Ties go to the smallest sequence, so the answer doesn't depend on hash order.
+    let max_sum = seq_sums
         .iter()
-        .map(|s| {
-            (
-                s,
-                seq_maps
-                    .iter()
-                    .map(|m| m.get(s).unwrap_or(&0))
-                    .map(|v| *v)
-                    .sum::<u64>(),
-            )
-        })
-        .max_by(|a, b| a.1.cmp(&b.1))
-        .map(|(seq, sum)| (*seq, sum))
-        .unwrap();
+        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+        .map(|(seq, sum)| (*seq, *sum))
+        .unwrap_or(((0, 0, 0, 0), 0));
 
     println!(
         "Best Sequence : {}",
         format!("{:?}", max_sum.0).as_str().cyan().bold()
     );
     println!("Best Sum      : {}", max_sum.1.to_string().green().bold());
+
+    let mut explanation = Explanation::default();
+    explanation.step("Best sequence", format!("{:?}", max_sum.0));
+    for (secret, seq_map) in first_secrets.iter().zip(seq_maps.iter()) {
+        explanation.step(
+            format!("Buyer {}", secret),
+            match seq_map.get(&max_sum.0) {
+                Some(price) => format!("sells for {}", price),
+                None => "never sees the sequence".to_string(),
+            },
+        );
+    }
+    return explanation.answer(max_sum.1);
 }
 
-fn get_seq_price_map(secret: u64) -> HashMap<(i64, i64, i64, i64), u64> {
+fn get_seq_price_map(secret: u64, num_secrets: usize) -> HashMap<(i64, i64, i64, i64), u64> {
     let mut seq_to_price: HashMap<(i64, i64, i64, i64), u64> = HashMap::new();
 
     let mut past_diffs: VecDeque<i64> = VecDeque::with_capacity(5);
     let mut working_secret = secret;
     let mut working_price = secret % 10;
 
-    for _ in 0..2000 {
+    for _ in 0..num_secrets {
         let next_secret = next_secret(working_secret);
         let next_price = next_secret % 10;
         let diff = next_price as i64 - working_price as i64;
@@ -8,30 +8,11 @@ use std::{
 use colored::Colorize;
 use itertools::Itertools;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day23.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 23, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 23, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{cancel, config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 23, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 23, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(23));
+    runner::run_parts(23, part_opt, || part1(&input), || part2(&input));
 }
 
 fn get_connections_from_file(input_file: &PathBuf) -> HashMap<String, HashSet<String>> {
@@ -69,7 +50,7 @@ fn get_connections_from_file(input_file: &PathBuf) -> HashMap<String, HashSet<St
     return connections;
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let connections = get_connections_from_file(input_file);
     println!("{:#?}", connections);
 
@@ -81,6 +62,12 @@ fn part1(input_file: &PathBuf) {
         "Number of triangles with at least one t: {}",
         triangles.len().to_string().green().bold()
     );
+
+    let mut explanation = Explanation::default();
+    for triangle in triangles.iter().sorted() {
+        explanation.step(triangle.join("-"), "all three connected");
+    }
+    return explanation.answer(triangles.len());
 }
 
 fn get_triangles_with_t(connections: &HashMap<String, HashSet<String>>) -> Vec<[String; 3]> {
@@ -91,6 +78,7 @@ fn get_triangles_with_t(connections: &HashMap<String, HashSet<String>>) -> Vec<[
 
         for neighbor in neighbors {
             let second_order_neighbors = connections.get(neighbor).unwrap();
+            metrics::incr("neighbor_pairs");
             let common_neighbors = neighbors.intersection(second_order_neighbors);
             for common_neigbor in common_neighbors {
                 let mut v = [key.clone(), neighbor.clone(), common_neigbor.clone()];
@@ -103,7 +91,7 @@ fn get_triangles_with_t(connections: &HashMap<String, HashSet<String>>) -> Vec<[
     return triangles.into_iter().collect();
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let connections = get_connections_from_file(input_file);
     println!("{:#?}", connections);
 
@@ -112,10 +100,11 @@ fn part2(input_file: &PathBuf) {
         .iter()
         .for_each(|v| println!("{:?}", v));
 
+    let empty_set: Vec<String> = Vec::new();
     let largest_connected_set = fully_connected_sets
         .iter()
         .max_by(|a, b| a.len().cmp(&b.len()))
-        .unwrap();
+        .unwrap_or(&empty_set); // Only empty if the search was cancelled early
 
     let password = largest_connected_set.iter().join(",");
 
@@ -128,6 +117,11 @@ fn part2(input_file: &PathBuf) {
             .bold()
     );
     println!("Password: {}", password.green().bold());
+
+    let mut explanation = Explanation::default();
+    explanation.step("Fully connected sets", fully_connected_sets.len());
+    explanation.step("Largest set size", largest_connected_set.len());
+    return explanation.answer(password);
 }
 
 fn get_fully_connected_sets_with_t(
@@ -136,11 +130,16 @@ fn get_fully_connected_sets_with_t(
     let mut fully_connected_sets: HashSet<Vec<String>> = HashSet::new();
 
     for key in connections.keys().filter(|k| k.starts_with("t")) {
+        if cancel::is_cancelled() {
+            break;
+        }
+
         let mut visited: HashSet<Vec<String>> = HashSet::new();
         let mut stack: Vec<(Vec<String>, HashSet<String>)> = Vec::new();
+        metrics::incr("stack_pushes");
         stack.push((vec![key.clone()], connections.get(key).unwrap().clone()));
 
-        while !stack.is_empty() {
+        while !stack.is_empty() && !cancel::tick() {
             let (mut current_set, working_neighbors) = stack.pop().unwrap();
 
             if visited.contains(&current_set) {
@@ -166,6 +165,7 @@ fn get_fully_connected_sets_with_t(
                 let mut next_set = current_set.clone();
                 next_set.push(neighbor.clone());
                 next_set.sort();
+                metrics::incr("stack_pushes");
                 stack.push((next_set, common_neigbors));
             }
         }
@@ -1,69 +1,308 @@
 use std::{
     fs::File,
     io::{BufRead, BufReader},
+    ops::Range,
     path::PathBuf,
 };
 
-use regex::Regex;
+use colored::Colorize;
+
+use crate::{config, explain::Explanation, metrics, runner};
 
 pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day3.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 3, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 3, Part 2");
-        part2(&input);
-        return;
-    }
-
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 3, Part 1");
-            part1(&input)
+    let input = input_opt.unwrap_or(config::default_input(3));
+    runner::run_parts(3, part_opt, || part1(&input), || part2(&input));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Ident,               // Letters and apostrophes, eg. "xmul" or "don't"
+    Number(Option<i64>), // None when the digits don't fit in an i64
+    LParen,
+    RParen,
+    Comma,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>, // Byte offsets within the line
+}
+
+fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let kind = if c.is_ascii_alphabetic() || c == '\'' {
+            while let Some((i, c)) = chars.next_if(|(_, c)| c.is_ascii_alphabetic() || *c == '\'') {
+                end = i + c.len_utf8();
+            }
+            TokenKind::Ident
+        } else if c.is_ascii_digit() {
+            while let Some((i, _)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                end = i + 1;
+            }
+            TokenKind::Number(line[start..end].parse::<i64>().ok())
+        } else {
+            match c {
+                '(' => TokenKind::LParen,
+                ')' => TokenKind::RParen,
+                ',' => TokenKind::Comma,
+                _ => TokenKind::Other,
+            }
+        };
+        tokens.push(Token {
+            kind,
+            span: start..end,
+        });
+    }
+    return tokens;
+}
+
+#[derive(Debug, Clone)]
+struct Machine {
+    enabled: bool,         // Toggled by do() and don't()
+    conditions: Vec<bool>, // Open if(..) blocks
+    acc: i64,
+}
+
+impl Machine {
+    fn new() -> Self {
+        return Machine {
+            enabled: true,
+            conditions: Vec::new(),
+            acc: 0,
+        };
+    }
+
+    fn is_active(&self) -> bool {
+        return self.enabled && self.conditions.iter().all(|c| *c);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum OpKind {
+    Value(fn(&[i64]) -> Option<i64>), // Result is added to the accumulator, None on overflow
+    Control(fn(&mut Machine, &[i64])),
+}
+
+#[derive(Clone, Copy)]
+struct Instruction {
+    name: &'static str,
+    arity: usize,
+    op: OpKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Executed(i64),
+    Control,
+    Disabled(&'static str),
+    Rejected(&'static str),
+    Overflow(&'static str), // Well formed, but the result doesn't fit in an i64
+}
+
+#[derive(Debug, Clone)]
+struct Record {
+    line: usize,
+    span: Range<usize>,
+    text: String,
+    status: Status,
+}
+
+struct Interpreter {
+    instructions: Vec<Instruction>,
+    machine: Machine,
+    records: Vec<Record>,
+}
+
+impl Interpreter {
+    fn new() -> Self {
+        return Interpreter {
+            instructions: Vec::new(),
+            machine: Machine::new(),
+            records: Vec::new(),
+        };
+    }
+
+    fn register(&mut self, name: &'static str, arity: usize, op: OpKind) {
+        self.instructions.push(Instruction { name, arity, op });
+    }
+
+    fn with_mul(mut self) -> Self {
+        self.register("mul", 2, OpKind::Value(|args| args[0].checked_mul(args[1])));
+        return self;
+    }
+
+    fn with_do_dont(mut self) -> Self {
+        self.register("do", 0, OpKind::Control(|m, _| m.enabled = true));
+        self.register("don't", 0, OpKind::Control(|m, _| m.enabled = false));
+        return self;
+    }
+
+    fn with_extensions(mut self) -> Self {
+        self.register("add", 2, OpKind::Value(|args| args[0].checked_add(args[1])));
+        self.register("sub", 2, OpKind::Value(|args| args[0].checked_sub(args[1])));
+        self.register(
+            "if",
+            1,
+            OpKind::Control(|m, args| m.conditions.push(args[0] != 0)),
+        );
+        self.register("endif", 0, OpKind::Control(|m, _| _ = m.conditions.pop()));
+        return self;
+    }
+
+    fn from_config(with_do_dont: bool) -> Self {
+        let mut interpreter = Interpreter::new().with_mul();
+        if with_do_dont {
+            interpreter = interpreter.with_do_dont();
         }
-        2 => {
-            println!("Running Day 3, Part 2");
-            part2(&input)
+        if config::day_param(3, "extended", false) {
+            interpreter = interpreter.with_extensions();
         }
-        _ => panic!("Invalid Part :("),
+        return interpreter;
     }
-}
 
-fn part1(input_file: &PathBuf) {
-    let input_file = File::open(input_file).expect(
-        format!(
-            "Could not open input file: {}",
-            input_file.to_string_lossy()
-        )
-        .as_str(),
-    );
-    let lines = BufReader::new(input_file).lines();
+    fn find_instruction(&self, ident: &str) -> Option<Instruction> {
+        // Corrupted memory can have junk right before a name, like "xmul(2,4)", so match on the
+        // end of the identifier and prefer the longest name.
+        return self
+            .instructions
+            .iter()
+            .filter(|ins| ident.ends_with(ins.name))
+            .max_by_key(|ins| ins.name.len())
+            .copied();
+    }
 
-    let re = Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
-    let mut sum = 0;
-    for line_res in lines {
-        let line = line_res.expect("Could not read line");
-        println!("{:?}", line);
-        let matches: Vec<(i32, i32)> = re
-            .captures_iter(line.as_str())
-            .map(|e| {
-                (
-                    e.get(1).unwrap().as_str().parse::<i32>().unwrap(),
-                    e.get(2).unwrap().as_str().parse::<i32>().unwrap(),
-                )
-            })
-            .collect();
-        println!("Matches found: {:?}", matches);
-        sum += matches.iter().map(|f| f.0 * f.1).sum::<i32>();
+    // Parses "(arg, ...)" starting at tokens[pos]. Returns the arguments and the index of the
+    // closing paren, or the reason and the index of the offending token.
+    fn parse_args(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        arity: usize,
+    ) -> Result<(Vec<i64>, usize), (&'static str, usize)> {
+        let mut pos = pos;
+        if tokens.get(pos).map(|t| t.kind) != Some(TokenKind::LParen) {
+            return Err(("expected '('", pos));
+        }
+        pos += 1;
+
+        let mut args: Vec<i64> = Vec::new();
+        for i in 0..arity {
+            if i > 0 {
+                if tokens.get(pos).map(|t| t.kind) != Some(TokenKind::Comma) {
+                    return Err(("expected ','", pos));
+                }
+                pos += 1;
+            }
+            match tokens.get(pos).map(|t| t.kind) {
+                Some(TokenKind::Number(Some(n))) => args.push(n),
+                Some(TokenKind::Number(None)) => return Err(("number too large", pos)),
+                _ => return Err(("expected a number", pos)),
+            }
+            pos += 1;
+        }
+
+        if tokens.get(pos).map(|t| t.kind) != Some(TokenKind::RParen) {
+            return Err(("expected ')'", pos));
+        }
+        return Ok((args, pos));
+    }
+
+    fn run_line(&mut self, line_idx: usize, line: &str) {
+        let tokens = tokenize(line);
+        let mut pos = 0;
+        while pos < tokens.len() {
+            let token = &tokens[pos];
+            let instruction = match token.kind {
+                TokenKind::Ident => self.find_instruction(&line[token.span.clone()]),
+                _ => None,
+            };
+            let Some(instruction) = instruction else {
+                pos += 1;
+                continue;
+            };
+
+            let start = token.span.end - instruction.name.len();
+            match self.parse_args(&tokens, pos + 1, instruction.arity) {
+                Ok((args, close)) => {
+                    let span = start..tokens[close].span.end;
+                    let text = line[span.clone()].to_string();
+                    let status = self.execute(&instruction, &args);
+                    self.records.push(Record {
+                        line: line_idx,
+                        span,
+                        text,
+                        status,
+                    });
+                    pos = close + 1;
+                }
+                Err((reason, failed)) => {
+                    // Resume at the offending token, it could start the next instruction.
+                    let end = tokens[failed - 1].span.end;
+                    metrics::incr("instructions_rejected");
+                    self.records.push(Record {
+                        line: line_idx,
+                        span: start..end,
+                        text: line[start..end].to_string(),
+                        status: Status::Rejected(reason),
+                    });
+                    pos = failed;
+                }
+            }
+        }
     }
 
-    println!("Sum: {}", sum);
+    fn execute(&mut self, instruction: &Instruction, args: &[i64]) -> Status {
+        metrics::incr("instructions_matched");
+        return match instruction.op {
+            OpKind::Control(op) => {
+                op(&mut self.machine, args);
+                Status::Control
+            }
+            OpKind::Value(_) if !self.machine.enabled => Status::Disabled("disabled by don't()"),
+            OpKind::Value(_) if !self.machine.is_active() => {
+                Status::Disabled("inside a false if()")
+            }
+            OpKind::Value(op) => match op(args) {
+                None => Status::Overflow("result overflows"),
+                Some(value) => match self.machine.acc.checked_add(value) {
+                    None => Status::Overflow("sum overflows"),
+                    Some(acc) => {
+                        self.machine.acc = acc;
+                        Status::Executed(value)
+                    }
+                },
+            },
+        };
+    }
+}
+
+// Reprints a line of memory with executed instructions in green, disabled ones in yellow and
+// everything the interpreter ignored dimmed.
+fn annotate_line(line: &str, records: &[Record]) -> String {
+    let mut annotated = String::new();
+    let mut cursor = 0;
+    for record in records.iter() {
+        let text = &line[record.span.clone()];
+        let colored = match record.status {
+            Status::Executed(_) | Status::Control => text.green().bold(),
+            Status::Disabled(_) => text.yellow(),
+            Status::Overflow(_) => text.red().bold(),
+            Status::Rejected(_) => continue, // Part of the noise
+        };
+        annotated += &line[cursor..record.span.start].dimmed().to_string();
+        annotated += &colored.to_string();
+        cursor = record.span.end;
+    }
+    annotated += &line[cursor..].dimmed().to_string();
+    return annotated;
 }
 
-fn part2(input_file: &PathBuf) {
+fn run(input_file: &PathBuf, interpreter: &mut Interpreter) -> Explanation {
     let input_file = File::open(input_file).expect(
         format!(
             "Could not open input file: {}",
@@ -73,42 +312,71 @@ fn part2(input_file: &PathBuf) {
     );
     let lines = BufReader::new(input_file).lines();
 
-    let re = Regex::new(r"mul\((\d+),(\d+)\)|do\(\)|don\'t\(\)").unwrap();
-    let do_str = "do()";
-    let dont_str = "don't()";
+    let annotate = config::day_param(3, "annotate", false);
+    if annotate {
+        println!(
+            "Legend: {} {} {} {}",
+            "executed".green().bold(),
+            "disabled".yellow(),
+            "overflow".red().bold(),
+            "ignored".dimmed()
+        );
+    }
 
-    let mut sum = 0;
-    let mut is_enabled = true;
-    for line_res in lines {
+    let mut explanation = Explanation::default();
+    for (line_idx, line_res) in lines.enumerate() {
         let line = line_res.expect("Could not read line");
-        println!("{:?}", line);
-        let matches = re.captures_iter(line.as_str());
-
-        let mut factor_list: Vec<(i32, i32)> = Vec::new();
-
-        for m in matches {
-            let matc = m.get(0).unwrap().as_str();
-            if matc == do_str {
-                is_enabled = true;
-                continue;
-            }
-
-            if matc == dont_str {
-                is_enabled = false;
-                continue;
-            }
+        if !annotate {
+            println!("{:?}", line);
+        }
 
-            if is_enabled {
-                factor_list.push((
-                    m.get(1).unwrap().as_str().parse::<i32>().unwrap(),
-                    m.get(2).unwrap().as_str().parse::<i32>().unwrap(),
-                ));
+        // The do()/don't() state carries over from the end of the previous line.
+        let enabled_at_start = interpreter.machine.enabled;
+        let first_record = interpreter.records.len();
+        interpreter.run_line(line_idx, &line);
+        if annotate {
+            let state = if enabled_at_start { "on " } else { "off" };
+            println!(
+                "[{}] {}",
+                state,
+                annotate_line(&line, &interpreter.records[first_record..])
+            );
+        }
+        let mut executed: Vec<&str> = Vec::new();
+        let mut rejected = 0;
+        let mut overflowed = 0;
+        for record in interpreter.records[first_record..].iter() {
+            let subject = format!("{} at {}:{:?}", record.text, record.line + 1, record.span);
+            match record.status {
+                Status::Executed(value) => {
+                    executed.push(&record.text);
+                    explanation.step(subject, value);
+                }
+                Status::Disabled(reason) => {
+                    explanation.step(subject, format!("skipped, {}", reason));
+                }
+                Status::Overflow(reason) => {
+                    overflowed += 1;
+                    explanation.step(subject, format!("skipped, {}", reason));
+                }
+                Status::Control => (),
+                Status::Rejected(_) => rejected += 1,
             }
         }
-
-        println!("{:?}", factor_list);
-        sum += factor_list.iter().map(|f| f.0 * f.1).sum::<i32>();
+        println!(
+            "Executed: {:?}, rejected: {}, overflowed: {}",
+            executed, rejected, overflowed
+        );
     }
 
-    println!("Sum: {}", sum);
+    println!("Sum: {}", interpreter.machine.acc);
+    return explanation.answer(interpreter.machine.acc);
+}
+
+fn part1(input_file: &PathBuf) -> Explanation {
+    return run(input_file, &mut Interpreter::from_config(false));
+}
+
+fn part2(input_file: &PathBuf) -> Explanation {
+    return run(input_file, &mut Interpreter::from_config(true));
 }
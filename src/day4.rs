@@ -1,71 +1,323 @@
 use std::{
-    fs::File,
+    collections::HashSet,
+    fs::{self, File},
     io::{BufRead, BufReader},
     path::PathBuf,
     vec,
 };
 
-static POSSIBLE_DIRS: [(i32, i32); 8] = [
-    (0, -1),  // up
-    (0, 1),   // down
-    (-1, 0),  // left
-    (1, 0),   // right
-    (1, 1),   // down right
-    (-1, -1), // up left
-    (1, -1),  // down left
-    (-1, 1),  // up right
-];
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{config, explain::Explanation, metrics, runner};
 
-static XMAS: [char; 4] = ['X', 'M', 'A', 'S'];
-static MAS: [char; 3] = ['M', 'A', 'S'];
+static POSSIBLE_DIRS: [(&str, (i32, i32)); 8] = [
+    ("up", (0, -1)),
+    ("down", (0, 1)),
+    ("left", (-1, 0)),
+    ("right", (1, 0)),
+    ("down_right", (1, 1)),
+    ("up_left", (-1, -1)),
+    ("up_right", (1, -1)),
+    ("down_left", (-1, 1)),
+];
 
 pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day4.txt"));
+    let input = input_opt.unwrap_or(config::default_input(4));
+    runner::run_parts(4, part_opt, || part1(&input), || part2(&input));
+}
+
+fn dir_name(dir: (i32, i32)) -> &'static str {
+    return POSSIBLE_DIRS
+        .iter()
+        .find(|(_, d)| *d == dir)
+        .map(|(name, _)| *name)
+        .unwrap_or("?");
+}
+
+#[derive(Debug, Clone)]
+struct Match {
+    word: String,
+    start: (usize, usize),
+    dir: Option<(i32, i32)>, // None for shape matches
+    cells: Vec<(usize, usize)>,
+}
+
+// A 2-D template of letters relative to its top left corner, eg. the X of two crossing MAS.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Shape {
+    cells: Vec<((i32, i32), char)>,
+}
+
+impl Shape {
+    // Rows separated by '/' or newlines, '.' marks cells that can hold anything.
+    // "M.S/.A./M.S" is the X-MAS from part 2.
+    fn from_mask(mask: &str) -> Self {
+        let mut cells: Vec<((i32, i32), char)> = Vec::new();
+        for (y, row) in mask.split(['/', '\n']).map(|r| r.trim()).enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c != '.' {
+                    cells.push(((x as i32, y as i32), c));
+                }
+            }
+        }
+        if cells.is_empty() {
+            panic!("Shape mask has no letters: {}", mask);
+        }
+        return Shape { cells };
+    }
+
+    fn x(word: &str) -> Self {
+        let len = word.chars().count() as i32;
+        let mut cells: Vec<((i32, i32), char)> = Vec::new();
+        for (i, c) in word.chars().enumerate() {
+            let i = i as i32;
+            cells.push(((i, i), c));
+            cells.push(((len - 1 - i, i), c));
+        }
+        return Shape::dedup(cells);
+    }
+
+    fn plus(word: &str) -> Self {
+        let mid = word.chars().count() as i32 / 2;
+        let mut cells: Vec<((i32, i32), char)> = Vec::new();
+        for (i, c) in word.chars().enumerate() {
+            let i = i as i32;
+            cells.push(((i, mid), c));
+            cells.push(((mid, i), c));
+        }
+        return Shape::dedup(cells);
+    }
+
+    fn dedup(mut cells: Vec<((i32, i32), char)>) -> Self {
+        cells.sort();
+        cells.dedup();
+        return Shape { cells };
+    }
 
-    if part_opt.is_none() {
-        println!("Running Day 4, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 4, Part 2");
-        part2(&input);
-        return;
+    fn from_config() -> Self {
+        let shape_file: Option<PathBuf> = config::day_param(4, "shape_file", None);
+        if let Some(shape_file) = shape_file {
+            let mask = fs::read_to_string(&shape_file).expect(
+                format!(
+                    "Could not read shape file: {}",
+                    shape_file.to_string_lossy()
+                )
+                .as_str(),
+            );
+            return Shape::from_mask(&mask);
+        }
+
+        let word: String = config::day_param(4, "shape_word", "MAS".to_string());
+        let shape: String = config::day_param(4, "shape", "x".to_string());
+        return match shape.as_str() {
+            "x" => Shape::x(&word),
+            "plus" => Shape::plus(&word),
+            mask => Shape::from_mask(mask),
+        };
+    }
+
+    // Moves the shape so its top left corner is at (0, 0).
+    fn normalized(mut self) -> Self {
+        let min_x = self.cells.iter().map(|((x, _), _)| *x).min().unwrap();
+        let min_y = self.cells.iter().map(|((_, y), _)| *y).min().unwrap();
+        for ((x, y), _) in self.cells.iter_mut() {
+            *x -= min_x;
+            *y -= min_y;
+        }
+        return Shape::dedup(self.cells);
+    }
+
+    // All distinct rotations and reflections of the shape.
+    fn orientations(&self) -> Vec<Shape> {
+        let mut seen: HashSet<Shape> = HashSet::new();
+        let mut ret: Vec<Shape> = Vec::new();
+        for flip in [false, true] {
+            let mut cells: Vec<((i32, i32), char)> = self
+                .cells
+                .iter()
+                .map(|((x, y), c)| (if flip { (-*x, *y) } else { (*x, *y) }, *c))
+                .collect();
+            for _ in 0..4 {
+                let shape = Shape {
+                    cells: cells.clone(),
+                }
+                .normalized();
+                if seen.insert(shape.clone()) {
+                    ret.push(shape);
+                }
+                cells = cells.iter().map(|((x, y), c)| ((-*y, *x), *c)).collect();
+            }
+        }
+        return ret;
+    }
+}
+
+struct WordSearch<'a> {
+    board: &'a Vec<Vec<char>>,
+    wrap: bool,
+    dirs: Vec<(i32, i32)>,
+}
+
+impl<'a> WordSearch<'a> {
+    fn from_config(board: &'a Vec<Vec<char>>) -> Self {
+        let dir_names: Vec<String> = config::day_param(
+            4,
+            "dirs",
+            POSSIBLE_DIRS
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        );
+        let dirs = dir_names
+            .iter()
+            .map(|name| {
+                POSSIBLE_DIRS
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, d)| *d)
+                    .unwrap_or_else(|| panic!("Unknown direction: {}", name))
+            })
+            .collect();
+
+        return WordSearch {
+            board,
+            wrap: config::day_param(4, "wrap", false),
+            dirs,
+        };
+    }
+
+    fn size(&self) -> (i32, i32) {
+        return (self.board[0].len() as i32, self.board.len() as i32);
+    }
+
+    // Resolves a position that may be off the board, wrapping around if enabled.
+    fn cell(&self, pos: (i32, i32)) -> Option<(usize, usize)> {
+        let (width, height) = self.size();
+        if self.wrap {
+            return Some((
+                pos.0.rem_euclid(width) as usize,
+                pos.1.rem_euclid(height) as usize,
+            ));
+        }
+        if pos.0 < 0 || pos.0 >= width || pos.1 < 0 || pos.1 >= height {
+            return None;
+        }
+        return Some((pos.0 as usize, pos.1 as usize));
+    }
+
+    fn match_cells(&self, cells: &Vec<((i32, i32), char)>) -> Option<Vec<(usize, usize)>> {
+        let mut ret: Vec<(usize, usize)> = Vec::new();
+        for (pos, c) in cells.iter() {
+            let (x, y) = self.cell(*pos)?;
+            if self.board[y][x] != *c {
+                return None;
+            }
+            ret.push((x, y));
+        }
+        return Some(ret);
+    }
+
+    fn find_word(&self, word: &str) -> Vec<Match> {
+        let (width, height) = self.size();
+        let mut ret: Vec<Match> = Vec::new();
+        let mut words_checked: u64 = 0;
+        for y in 0..height {
+            for x in 0..width {
+                for dir in self.dirs.iter() {
+                    words_checked += 1;
+                    let cells: Vec<((i32, i32), char)> = word
+                        .chars()
+                        .enumerate()
+                        .map(|(i, c)| ((x + dir.0 * i as i32, y + dir.1 * i as i32), c))
+                        .collect();
+                    if let Some(cells) = self.match_cells(&cells) {
+                        ret.push(Match {
+                            word: word.to_string(),
+                            start: (x as usize, y as usize),
+                            dir: Some(*dir),
+                            cells,
+                        });
+                    }
+                }
+            }
+        }
+        metrics::add("words_checked", words_checked);
+        return ret;
     }
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 4, Part 1");
-            part1(&input)
+    fn find_shape(&self, shape: &Shape) -> Vec<Match> {
+        let orientations = shape.orientations();
+        let (width, height) = self.size();
+        let mut ret: Vec<Match> = Vec::new();
+        let mut shapes_checked: u64 = 0;
+        for y in 0..height {
+            for x in 0..width {
+                for orientation in orientations.iter() {
+                    shapes_checked += 1;
+                    let cells: Vec<((i32, i32), char)> = orientation
+                        .cells
+                        .iter()
+                        .map(|((dx, dy), c)| ((x + dx, y + dy), *c))
+                        .collect();
+                    if let Some(cells) = self.match_cells(&cells) {
+                        ret.push(Match {
+                            word: orientation.cells.iter().map(|(_, c)| c).collect(),
+                            start: (x as usize, y as usize),
+                            dir: None,
+                            cells,
+                        });
+                    }
+                }
+            }
         }
-        2 => {
-            println!("Running Day 4, Part 2");
-            part2(&input)
+        metrics::add("shapes_checked", shapes_checked);
+        return ret;
+    }
+}
+
+fn describe_match(m: &Match) -> String {
+    return match m.dir {
+        Some(dir) => format!("{} at {:?} going {}", m.word, m.start, dir_name(dir)),
+        None => format!("shape at {:?}", m.start),
+    };
+}
+
+fn print_matches(matches: &Vec<Match>) {
+    if config::get().verbose {
+        for m in matches.iter() {
+            println!("{}", describe_match(m));
         }
-        _ => panic!("Invalid Part :("),
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let board = create_board(input_file);
-    let mut empty_board = create_empty_board(&board);
 
     pretty_print_board(&board);
     println!();
 
-    let board_size = (board[0].len(), board.len());
-    let mut xmas_count = 0;
-    for x in 0..board_size.0 {
-        for y in 0..board_size.1 {
-            if board[y][x] == 'X' {
-                let xmas_dirs = check_xmas_in_all_dirs(&board, (x, y));
-                xmas_count += xmas_dirs.len() as i32;
-                fill_xmas_in_empty_board(&mut empty_board, &board, (x, y), xmas_dirs);
-            }
+    let search = WordSearch::from_config(&board);
+    let words: Vec<String> = config::day_param(4, "words", vec!["XMAS".to_string()]);
+    let mut overlay = Overlay::new(&board);
+    let mut explanation = Explanation::default();
+    let mut words_found = 0;
+    for word in words.iter() {
+        let matches = search.find_word(word);
+        overlay.add(&matches);
+        print_matches(&matches);
+        for m in matches.iter() {
+            explanation.step(describe_match(m), format!("cells {:?}", m.cells));
         }
+        if words.len() > 1 {
+            println!("{} count: {}", word, matches.len());
+        }
+        words_found += matches.len();
     }
 
-    pretty_print_board(&empty_board);
-    println!("XMAS count: {}", xmas_count);
+    overlay.export();
+    println!("Words found: {}", words_found);
+    return explanation.answer(words_found);
 }
 
 fn create_board(input_file: &PathBuf) -> Vec<Vec<char>> {
@@ -88,61 +340,166 @@ fn create_board(input_file: &PathBuf) -> Vec<Vec<char>> {
     return board;
 }
 
-fn create_empty_board(board: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let empty_board: Vec<Vec<char>> = vec![vec!['.'; board[0].len()]; board.len()];
-    return empty_board;
+#[derive(Debug, Serialize)]
+struct OverlayRecord {
+    word: String,
+    start: (usize, usize),
+    direction: Option<&'static str>, // None for shape matches
 }
 
-fn fill_xmas_in_empty_board(
-    empty_board: &mut Vec<Vec<char>>,
-    board: &Vec<Vec<char>>,
-    start: (usize, usize),
-    xmas_dirs: Vec<(i32, i32)>,
-) {
-    for dir in xmas_dirs {
-        let (mut x, mut y) = (start.0 as i32, start.1 as i32);
-        for _ in XMAS {
-            let (x_idx, y_idx) = (x as usize, y as usize);
-            empty_board[y_idx][x_idx] = board[y_idx][x_idx];
-            x += dir.0;
-            y += dir.1;
-        }
-    }
+// Every match found on a board, and how many matches cover each cell.
+struct Overlay<'a> {
+    board: &'a Vec<Vec<char>>,
+    matches: Vec<Match>,
+    coverage: Vec<Vec<u32>>,
 }
 
-fn check_xmas_in_all_dirs(board: &Vec<Vec<char>>, start: (usize, usize)) -> Vec<(i32, i32)> {
-    if board[start.1][start.0] != 'X' {
-        return Vec::new();
+impl<'a> Overlay<'a> {
+    fn new(board: &'a Vec<Vec<char>>) -> Self {
+        return Overlay {
+            board,
+            matches: Vec::new(),
+            coverage: vec![vec![0; board[0].len()]; board.len()],
+        };
     }
 
-    let mut ret: Vec<(i32, i32)> = Vec::new();
-    for dir in POSSIBLE_DIRS {
-        if is_xmas_in_dir(board, start, dir) {
-            ret.push(dir);
+    fn add(&mut self, matches: &Vec<Match>) {
+        for m in matches.iter() {
+            for (x, y) in m.cells.iter() {
+                self.coverage[*y][*x] += 1;
+            }
+            self.matches.push(m.clone());
         }
     }
-    return ret;
-}
 
-fn is_xmas_in_dir(board: &Vec<Vec<char>>, start: (usize, usize), dir: (i32, i32)) -> bool {
-    let mut x = start.0 as i32;
-    let mut y = start.1 as i32;
+    fn records(&self) -> Vec<OverlayRecord> {
+        return self
+            .matches
+            .iter()
+            .map(|m| OverlayRecord {
+                word: m.word.clone(),
+                start: m.start,
+                direction: m.dir.map(dir_name),
+            })
+            .collect();
+    }
 
-    let board_size = (board[0].len() as i32, board.len() as i32);
+    fn max_coverage(&self) -> u32 {
+        return *self.coverage.iter().flatten().max().unwrap_or(&0);
+    }
+
+    // Only the matched letters, everything else is '.'
+    fn render_text(&self) -> String {
+        let mut ret = String::new();
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                ret.push(if self.coverage[y][x] > 0 { *c } else { '.' });
+            }
+            ret.push('\n');
+        }
+        return ret;
+    }
 
-    for c in XMAS {
-        if x < 0 || x >= board_size.0 || y < 0 || y >= board_size.1 {
-            return false;
+    fn render_color(&self) -> String {
+        let mut ret = String::new();
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let c = c.to_string();
+                let colored = if self.coverage[y][x] > 0 {
+                    c.green().bold()
+                } else {
+                    c.dimmed()
+                };
+                ret += &colored.to_string();
+            }
+            ret.push('\n');
         }
+        return ret;
+    }
 
-        if board[y as usize][x as usize] != c {
-            return false;
+    // Number of matches covering each cell, 1-9 then '+' for anything denser.
+    fn render_heatmap(&self) -> String {
+        let max = self.max_coverage().max(1);
+        let mut ret = String::new();
+        for row in self.coverage.iter() {
+            for count in row.iter() {
+                let cell = match count {
+                    0 => ".".to_string(),
+                    1..=9 => count.to_string(),
+                    _ => "+".to_string(),
+                };
+                let colored = match count * 3 / max {
+                    0 if *count == 0 => cell.dimmed(),
+                    0 => cell.green(),
+                    1 => cell.yellow(),
+                    _ => cell.red().bold(),
+                };
+                ret += &colored.to_string();
+            }
+            ret.push('\n');
         }
-        x += dir.0;
-        y += dir.1;
+        return ret;
     }
 
-    return true;
+    // Standalone page, matched cells are shaded by how many matches cover them.
+    fn render_html(&self) -> String {
+        let max = self.max_coverage().max(1);
+        let mut ret = String::new();
+        ret += "<!DOCTYPE html>\n<html>\n<body>\n<pre style=\"font-family: monospace\">\n";
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let c = match c {
+                    '<' => "&lt;".to_string(),
+                    '>' => "&gt;".to_string(),
+                    '&' => "&amp;".to_string(),
+                    _ => c.to_string(),
+                };
+                let count = self.coverage[y][x];
+                if count == 0 {
+                    ret += &format!("<span style=\"color: #999\">{}</span>", c);
+                } else {
+                    let alpha = 0.2 + 0.8 * count as f64 / max as f64;
+                    ret += &format!(
+                        "<span title=\"{} matches\" style=\"background: rgba(255, 80, 0, {:.2})\">{}</span>",
+                        count, alpha, c
+                    );
+                }
+            }
+            ret.push('\n');
+        }
+        ret += "</pre>\n</body>\n</html>\n";
+        return ret;
+    }
+
+    fn render(&self, format: &str) -> String {
+        return match format {
+            "text" => self.render_text(),
+            "color" => self.render_color(),
+            "heatmap" => self.render_heatmap(),
+            "html" => self.render_html(),
+            "json" => {
+                serde_json::to_string_pretty(&self.records()).expect("Could not serialize overlay")
+                    + "\n"
+            }
+            _ => panic!("Unknown overlay format: {}", format),
+        };
+    }
+
+    fn export(&self) {
+        let format: String = config::day_param(4, "overlay", "text".to_string());
+        let rendered = self.render(&format);
+
+        let overlay_file: Option<PathBuf> = config::day_param(4, "overlay_file", None);
+        match overlay_file {
+            Some(path) => {
+                fs::write(&path, rendered).expect(
+                    format!("Could not write overlay file: {}", path.to_string_lossy()).as_str(),
+                );
+                println!("Wrote {} overlay to {}", format, path.to_string_lossy());
+            }
+            None => print!("{}", rendered),
+        }
+    }
 }
 
 fn pretty_print_board(board: &Vec<Vec<char>>) {
@@ -151,80 +508,26 @@ fn pretty_print_board(board: &Vec<Vec<char>>) {
     }
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let board = create_board(input_file);
-    let mut empty_board = create_empty_board(&board);
 
     pretty_print_board(&board);
     println!();
 
-    let board_size = (board[0].len(), board.len());
-    let mut x_mas_count = 0;
+    let search = WordSearch::from_config(&board);
+    let shape = Shape::from_config();
+    let matches = search.find_shape(&shape);
+    let mut overlay = Overlay::new(&board);
+    overlay.add(&matches);
+    print_matches(&matches);
 
-    for x in 0..board_size.0 {
-        for y in 0..board_size.1 {
-            if board[y][x] == 'A' {
-                if is_x_mas(&board, (x, y)) {
-                    fill_x_mas_in_empty_board(&mut empty_board, &board, (x, y));
-                    x_mas_count += 1;
-                }
-            }
-        }
+    let mut explanation = Explanation::default();
+    for m in matches.iter() {
+        explanation.step(describe_match(m), format!("cells {:?}", m.cells));
     }
+    let x_mas_count = matches.len();
 
-    pretty_print_board(&empty_board);
+    overlay.export();
     println!("X-MAS count: {}", x_mas_count);
-}
-
-fn is_x_mas(board: &Vec<Vec<char>>, middle: (usize, usize)) -> bool {
-    let (x, y) = (middle.0 as i32, middle.1 as i32);
-    if board[y as usize][x as usize] != 'A' {
-        return false;
-    }
-
-    let is_left_diagonal_mas = is_mas_in_dir(board, (x - 1, y - 1), (1, 1))
-        || is_mas_in_dir(board, (x + 1, y + 1), (-1, -1));
-
-    let is_right_diagonal_mas = is_mas_in_dir(board, (x + 1, y - 1), (-1, 1))
-        || is_mas_in_dir(board, (x - 1, y + 1), (1, -1));
-
-    return is_left_diagonal_mas && is_right_diagonal_mas;
-}
-
-fn is_mas_in_dir(board: &Vec<Vec<char>>, start: (i32, i32), dir: (i32, i32)) -> bool {
-    let mut x = start.0;
-    let mut y = start.1;
-    for c in MAS {
-        if x < 0 || x >= board[0].len() as i32 || y < 0 || y >= board.len() as i32 {
-            return false;
-        }
-        if board[y as usize][x as usize] != c {
-            return false;
-        }
-        x += dir.0;
-        y += dir.1;
-    }
-    return true;
-}
-
-fn fill_x_mas_in_empty_board(
-    empty_board: &mut Vec<Vec<char>>,
-    board: &Vec<Vec<char>>,
-    middle: (usize, usize),
-) {
-    let (mut x, mut y) = (middle.0 - 1, middle.1 - 1);
-    let dir = (1, 1);
-    for _ in MAS {
-        empty_board[y][x] = board[y][x];
-        x += dir.0;
-        y += dir.1;
-    }
-
-    let (mut x, mut y) = ((middle.0 + 1) as i32, (middle.1 - 1) as i32);
-    let dir = (-1 as i32, 1 as i32);
-    for _ in MAS {
-        empty_board[y as usize][x as usize] = board[y as usize][x as usize];
-        x += dir.0;
-        y += dir.1;
-    }
+    return explanation.answer(x_mas_count);
 }
@@ -1,52 +1,245 @@
 use std::{
     collections::{HashMap, HashSet},
-    fs::File,
+    fs::{self, File},
     io::{BufRead, BufReader},
     path::PathBuf,
 };
 
+use crate::{config, explain::Explanation, metrics, runner};
+
 pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day5.txt"));
+    let input = input_opt.unwrap_or(config::default_input(5));
+    runner::run_parts(5, part_opt, || part1(&input), || part2(&input));
+}
 
-    if part_opt.is_none() {
-        println!("Running Day 5, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 5, Part 2");
-        part2(&input);
-        return;
+// Ordering rules as a graph with an edge from each page to the pages that must come after it.
+struct PrecedenceGraph {
+    successors: HashMap<i32, HashSet<i32>>,
+    predecessors: HashMap<i32, HashSet<i32>>,
+}
+
+#[derive(Debug)]
+struct TopoOrder {
+    order: Vec<i32>,
+    ambiguous: Option<Vec<i32>>, // First set of pages that could have gone in either order
+}
+
+impl PrecedenceGraph {
+    fn from_reverse_rules(reverse_rules: &HashMap<i32, HashSet<i32>>) -> Self {
+        let mut successors: HashMap<i32, HashSet<i32>> = HashMap::new();
+        for (page, before) in reverse_rules.iter() {
+            for b in before.iter() {
+                successors.entry(*b).or_default().insert(*page);
+            }
+        }
+        return PrecedenceGraph {
+            successors,
+            predecessors: reverse_rules.clone(),
+        };
+    }
+
+    fn pages(&self) -> Vec<i32> {
+        let mut pages: Vec<i32> = self
+            .successors
+            .keys()
+            .chain(self.predecessors.keys())
+            .copied()
+            .collect::<HashSet<i32>>()
+            .into_iter()
+            .collect();
+        pages.sort();
+        return pages;
+    }
+
+    // Kahn's algorithm on the subgraph induced by pages. Ties are broken by the position in pages
+    // so an already valid update keeps its order. Returns the pages of a cycle if there is one.
+    fn topological_sort(&self, pages: &Vec<i32>) -> Result<TopoOrder, Vec<i32>> {
+        let page_set: HashSet<i32> = pages.iter().copied().collect();
+        let mut in_degree: HashMap<i32, usize> = pages
+            .iter()
+            .map(|p| {
+                let count = self
+                    .predecessors
+                    .get(p)
+                    .map_or(0, |before| before.intersection(&page_set).count());
+                (*p, count)
+            })
+            .collect();
+
+        let mut order: Vec<i32> = Vec::new();
+        let mut ambiguous: Option<Vec<i32>> = None;
+        while order.len() < pages.len() {
+            metrics::incr("topo_steps");
+            let ready: Vec<i32> = pages
+                .iter()
+                .filter(|p| in_degree.get(p) == Some(&0))
+                .copied()
+                .collect();
+            if ready.is_empty() {
+                let remaining: HashSet<i32> = in_degree.keys().copied().collect();
+                return Err(self.find_cycle(&remaining));
+            }
+            if ready.len() > 1 && ambiguous.is_none() {
+                ambiguous = Some(ready.clone());
+            }
+
+            let next = ready[0];
+            in_degree.remove(&next);
+            order.push(next);
+            for after in self.successors.get(&next).into_iter().flatten() {
+                if let Some(count) = in_degree.get_mut(after) {
+                    *count -= 1;
+                }
+            }
+        }
+        return Ok(TopoOrder { order, ambiguous });
+    }
+
+    // Every page left over by Kahn's algorithm has a predecessor that is also left over, so
+    // walking backwards from any of them has to loop.
+    fn find_cycle(&self, remaining: &HashSet<i32>) -> Vec<i32> {
+        let mut path: Vec<i32> = vec![*remaining.iter().min().expect("No pages left")];
+        loop {
+            let curr = *path.last().unwrap();
+            let prev = *self.predecessors[&curr]
+                .iter()
+                .filter(|p| remaining.contains(p))
+                .min()
+                .expect("Page left over without a predecessor");
+            if let Some(idx) = path.iter().position(|p| *p == prev) {
+                let mut cycle = path[idx..].to_vec();
+                cycle.reverse();
+                return cycle;
+            }
+            path.push(prev);
+        }
     }
+}
+
+impl PrecedenceGraph {
+    // Graphviz source for the whole rule graph, or only the pages of an update. Edges the update
+    // violates (the later page comes first) are drawn in red.
+    fn to_dot(&self, update: Option<&Vec<i32>>) -> String {
+        let positions: HashMap<i32, usize> = match update {
+            Some(pages) => pages.iter().enumerate().map(|(i, p)| (*p, i)).collect(),
+            None => HashMap::new(),
+        };
+        let pages = match update {
+            Some(pages) => pages.clone(),
+            None => self.pages(),
+        };
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 5, Part 1");
-            part1(&input)
+        let mut dot = String::from("digraph rules {\n    rankdir=LR;\n");
+        for page in pages.iter() {
+            match positions.get(page) {
+                Some(pos) => {
+                    dot += &format!("    \"{}\" [label=\"{}\\n#{}\"];\n", page, page, pos + 1)
+                }
+                None => dot += &format!("    \"{}\";\n", page),
+            }
         }
-        2 => {
-            println!("Running Day 5, Part 2");
-            part2(&input)
+
+        for from in pages.iter() {
+            let mut after: Vec<i32> = self
+                .successors
+                .get(from)
+                .into_iter()
+                .flatten()
+                .filter(|to| update.is_none() || positions.contains_key(to))
+                .copied()
+                .collect();
+            after.sort();
+            for to in after {
+                let violated = update.is_some() && positions[&to] < positions[from];
+                if violated {
+                    dot += &format!("    \"{}\" -> \"{}\" [color=red, penwidth=2];\n", from, to);
+                } else {
+                    dot += &format!("    \"{}\" -> \"{}\";\n", from, to);
+                }
+            }
         }
-        _ => panic!("Invalid Part :("),
+        dot += "}\n";
+        return dot;
+    }
+}
+
+fn export_dot(graph: &PrecedenceGraph, book: &Vec<Vec<i32>>) {
+    let dot_file: Option<PathBuf> = config::day_param(5, "dot_file", None);
+    let Some(dot_file) = dot_file else {
+        return;
+    };
+
+    // Updates are numbered from 1 in the order they appear in the input.
+    let update_num: Option<usize> = config::day_param(5, "dot_update", None);
+    let update = update_num.map(|n| {
+        book.get(n.wrapping_sub(1))
+            .unwrap_or_else(|| panic!("No update number {}, there are {}", n, book.len()))
+    });
+
+    fs::write(&dot_file, graph.to_dot(update))
+        .expect(format!("Could not write DOT file: {}", dot_file.to_string_lossy()).as_str());
+    match update {
+        Some(pages) => println!(
+            "Wrote graph for update {:?} to {}",
+            pages,
+            dot_file.to_string_lossy()
+        ),
+        None => println!("Wrote rule graph to {}", dot_file.to_string_lossy()),
+    }
+    println!();
+}
+
+fn format_cycle(cycle: &Vec<i32>) -> String {
+    let mut pages: Vec<String> = cycle.iter().map(|p| p.to_string()).collect();
+    pages.push(cycle[0].to_string());
+    return pages.join(" -> ");
+}
+
+fn report_rule_cycles(graph: &PrecedenceGraph) {
+    // The full rule set may well be cyclic, only the pages within an update need an order.
+    if let Err(cycle) = graph.topological_sort(&graph.pages()) {
+        println!(
+            "Rules contain a cycle, pages are only ordered within an update: {}",
+            format_cycle(&cycle)
+        );
+        println!();
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let (rules, book) = get_rules_and_book(input_file);
     println!();
     let reverse_rules = generate_reverse_rules_index(rules);
     println!("Reverse Rules: {:?}", reverse_rules);
     println!();
+    let graph = PrecedenceGraph::from_reverse_rules(&reverse_rules);
+    report_rule_cycles(&graph);
+    export_dot(&graph, &book);
 
+    let mut explanation = Explanation::default();
     let mut sum = 0;
     for pages in book {
         if are_pages_valid(&pages, &reverse_rules) {
             println!("Valid Pages: {:?}", pages);
-            sum += pages[pages.len() / 2]
+            if let Ok(TopoOrder {
+                ambiguous: Some(ready),
+                ..
+            }) = graph.topological_sort(&pages)
+            {
+                println!("  Ambiguous ordering, no rule between pages {:?}", ready);
+            }
+            sum += pages[pages.len() / 2];
+            explanation.step(
+                format!("{:?}", pages),
+                format!("valid, middle page {}", pages[pages.len() / 2]),
+            );
+        } else {
+            explanation.step(format!("{:?}", pages), "invalid");
         }
     }
 
     println!("Sum: {:?}", sum);
+    return explanation.answer(sum);
 }
 
 fn get_rules_and_book(input_file: &PathBuf) -> (Vec<(i32, i32)>, Vec<Vec<i32>>) {
@@ -110,60 +303,270 @@ fn generate_reverse_rules_index(rules: Vec<(i32, i32)>) -> HashMap<i32, HashSet<
 }
 
 fn are_pages_valid(pages: &Vec<i32>, reverse_rules: &HashMap<i32, HashSet<i32>>) -> bool {
+    let mut rule_checks: u64 = 0;
     for i in 0..pages.len() - 1 {
         for j in (i + 1)..pages.len() {
             let p1 = pages[i];
             let p2 = pages[j];
+            rule_checks += 1;
 
             if reverse_rules.contains_key(&p1) && reverse_rules[&p1].contains(&p2) {
                 // Found an invalid page sequence.
+                metrics::add("rule_checks", rule_checks);
                 return false;
             }
         }
     }
 
+    metrics::add("rule_checks", rule_checks);
     return true;
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let (rules, book) = get_rules_and_book(input_file);
     println!();
     let reverse_rules = generate_reverse_rules_index(rules);
     println!("Reverse Rules: {:?}", reverse_rules);
     println!();
+    let graph = PrecedenceGraph::from_reverse_rules(&reverse_rules);
+    report_rule_cycles(&graph);
+    export_dot(&graph, &book);
 
+    let repair_mode: Option<RepairMode> = config::day_param::<Option<String>>(5, "repair", None)
+        .map(|mode| RepairMode::from_str(&mode));
+    let mut explanation = Explanation::default();
     let mut sum = 0;
     for mut pages in book {
         if are_pages_valid(&pages, &reverse_rules) {
             continue;
         }
 
-        fix_invalid_pages(&mut pages, &reverse_rules);
+        let unfixed = pages.clone();
+        let original = format!("{:?}", pages);
+        if let Err(cycle) = fix_invalid_pages(&mut pages, &graph) {
+            println!(
+                "Can't fix pages {}, rules form a cycle: {}",
+                original,
+                format_cycle(&cycle)
+            );
+            explanation.step(
+                original,
+                format!("unfixable, cycle {}", format_cycle(&cycle)),
+            );
+            continue;
+        }
         println!("Fixed Pages: {:?}", pages);
-        sum += pages[pages.len() / 2]
+        if let Some(mode) = repair_mode {
+            let repair = repair_pages(&unfixed, &graph, &reverse_rules, mode);
+            println!(
+                "  Fewest {} ({}): {} => {:?}",
+                mode,
+                repair.script.len(),
+                repair.script.join(", "),
+                repair.result
+            );
+            explanation.step(
+                format!("{} fewest {}", original, mode),
+                format!("{} => {:?}", repair.script.join(", "), repair.result),
+            );
+        }
+        sum += pages[pages.len() / 2];
+        explanation.step(
+            original,
+            format!(
+                "fixed to {:?}, middle page {}",
+                pages,
+                pages[pages.len() / 2]
+            ),
+        );
+    }
+    println!("Sum: {:?}", sum);
+    return explanation.answer(sum);
+}
+
+fn fix_invalid_pages(pages: &mut Vec<i32>, graph: &PrecedenceGraph) -> Result<(), Vec<i32>> {
+    let sorted = graph.topological_sort(pages)?;
+    if let Some(ready) = sorted.ambiguous {
+        println!("  Ambiguous ordering, no rule between pages {:?}", ready);
     }
-    println!("Sum: {:?}", sum)
+    *pages = sorted.order;
+    return Ok(());
 }
 
-fn fix_invalid_pages(pages: &mut Vec<i32>, reverse_rules: &HashMap<i32, HashSet<i32>>) {
-    for start_idx in 0..pages.len() {
-        let mut curr_idx: usize = start_idx + 1;
-        while curr_idx < pages.len() {
-            let p1 = pages[start_idx];
-            let p2 = pages[curr_idx];
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepairMode {
+    Moves,    // Fewest pages picked up and put back somewhere else
+    Removals, // Fewest pages dropped from the update
+}
 
-            if reverse_rules.contains_key(&p1) && reverse_rules[&p1].contains(&p2) {
-                // Swap the offending entries fixing this particular sequence.
-                pages[start_idx] = p2;
-                pages[curr_idx] = p1;
+impl RepairMode {
+    fn from_str(s: &str) -> Self {
+        return match s {
+            "moves" => RepairMode::Moves,
+            "removals" => RepairMode::Removals,
+            _ => panic!("Unknown repair mode: {}", s),
+        };
+    }
+}
+
+impl std::fmt::Display for RepairMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            RepairMode::Moves => write!(f, "moves"),
+            RepairMode::Removals => write!(f, "removals"),
+        };
+    }
+}
+
+#[derive(Debug)]
+struct Repair {
+    script: Vec<String>,
+    result: Vec<i32>,
+}
 
-                // reset curr_idx to start_idx + 1 and continue checking for invalid sequences.
-                curr_idx = start_idx + 1;
-                continue;
+impl PrecedenceGraph {
+    // must_precede[i][j] is set when pages[i] has to come before pages[j], directly or through
+    // other pages of the update.
+    fn closure_within(&self, pages: &Vec<i32>) -> Vec<Vec<bool>> {
+        let n = pages.len();
+        let mut must_precede = vec![vec![false; n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                must_precede[i][j] = self
+                    .successors
+                    .get(&pages[i])
+                    .is_some_and(|after| after.contains(&pages[j]));
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    if must_precede[i][k] && must_precede[k][j] {
+                        must_precede[i][j] = true;
+                    }
+                }
             }
+        }
+        return must_precede;
+    }
+}
 
-            // No rules violated, move on to the next page.
-            curr_idx += 1;
+// Largest set of indices with no conflicting pair between them. Updates are short, so a branch
+// and bound over the most conflicted page is plenty.
+fn largest_compatible_set(conflicts: &Vec<Vec<bool>>) -> Vec<usize> {
+    fn search(
+        candidates: Vec<usize>,
+        chosen: &mut Vec<usize>,
+        best: &mut Vec<usize>,
+        conflicts: &Vec<Vec<bool>>,
+    ) {
+        metrics::incr("repair_branches");
+        if chosen.len() + candidates.len() <= best.len() {
+            return; // Can't beat the best set found so far.
+        }
+
+        let degree = |v: &usize| candidates.iter().filter(|u| conflicts[*v][**u]).count();
+        let most_conflicted = candidates.iter().copied().max_by_key(degree);
+        match most_conflicted {
+            Some(v) if degree(&v) > 0 => {
+                // Either keep v and drop everything it conflicts with, or drop v.
+                let without_neighbours = candidates
+                    .iter()
+                    .copied()
+                    .filter(|u| *u != v && !conflicts[v][*u])
+                    .collect();
+                chosen.push(v);
+                search(without_neighbours, chosen, best, conflicts);
+                chosen.pop();
+
+                let without_v = candidates.iter().copied().filter(|u| *u != v).collect();
+                search(without_v, chosen, best, conflicts);
+            }
+            _ => {
+                // No conflicts left, keep everything.
+                let mut found = chosen.clone();
+                found.extend(candidates);
+                found.sort();
+                *best = found;
+            }
         }
     }
+
+    let mut best: Vec<usize> = Vec::new();
+    search(
+        (0..conflicts.len()).collect(),
+        &mut Vec::new(),
+        &mut best,
+        conflicts,
+    );
+    return best;
+}
+
+fn repair_pages(
+    pages: &Vec<i32>,
+    graph: &PrecedenceGraph,
+    reverse_rules: &HashMap<i32, HashSet<i32>>,
+    mode: RepairMode,
+) -> Repair {
+    let n = pages.len();
+    let violates = |i: usize, j: usize| {
+        reverse_rules
+            .get(&pages[i])
+            .is_some_and(|before| before.contains(&pages[j]))
+    };
+
+    if mode == RepairMode::Removals {
+        // Whatever is left has to pass are_pages_valid, so only direct rules matter.
+        let conflicts: Vec<Vec<bool>> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| i < j && violates(i, j) || j < i && violates(j, i))
+                    .collect()
+            })
+            .collect();
+        let kept = largest_compatible_set(&conflicts);
+        return Repair {
+            script: (0..n)
+                .filter(|i| !kept.contains(i))
+                .map(|i| format!("remove {} (#{})", pages[i], i + 1))
+                .collect(),
+            result: kept.iter().map(|i| pages[*i]).collect(),
+        };
+    }
+
+    // Pages that stay put must not be out of order even through pages that get moved, otherwise
+    // there is nowhere to put the moved page back.
+    let mut must_precede = graph.closure_within(pages);
+    let conflicts: Vec<Vec<bool>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| i < j && must_precede[j][i] || j < i && must_precede[i][j])
+                .collect()
+        })
+        .collect();
+    let kept = largest_compatible_set(&conflicts);
+    for pair in kept.windows(2) {
+        must_precede[pair[0]][pair[1]] = true;
+    }
+
+    // Place everything with the kept pages pinned in order, ties go to the original position.
+    let mut order: Vec<usize> = Vec::new();
+    let mut placed = vec![false; n];
+    while order.len() < n {
+        let next = (0..n)
+            .find(|j| !placed[*j] && (0..n).all(|i| placed[i] || !must_precede[i][*j]))
+            .expect("Kept pages contradict the rules");
+        placed[next] = true;
+        order.push(next);
+    }
+
+    return Repair {
+        script: order
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| !kept.contains(i))
+            .map(|(pos, i)| format!("move {} from #{} to #{}", pages[*i], i + 1, pos + 1))
+            .collect(),
+        result: order.iter().map(|i| pages[*i]).collect(),
+    };
 }
@@ -1,34 +1,15 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
     path::PathBuf,
 };
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day6.txt"));
+use crate::{cancel, config, explain::Explanation, metrics, runner};
 
-    if part_opt.is_none() {
-        println!("Running Day 6, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 6, Part 2");
-        part2(&input);
-        return;
-    }
-
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 6, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 6, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(6));
+    runner::run_parts(6, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -38,11 +19,138 @@ enum Cell {
     Guard,
     Obstacle,
     AddedObstacle, // Used for pretty printing only, not for logic. Use Obstacle for logic instead.
+    TurnPoint,     // Used for pretty printing only
+    Teleporter(char), // Paired with the other cell with the same letter
 }
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Completion {
     OutOfBounds,
     Loop,
+    Cancelled,
+    Collision, // Ran head first into another guard
+}
+
+// Facing directions in turn right order: up, right, down, left.
+static DIRS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn dir_index(facing: (i32, i32)) -> usize {
+    return DIRS
+        .iter()
+        .position(|d| *d == facing)
+        .expect("Invalid guard facing direction");
+}
+
+// Compact set of (cell, facing) states, cleared by only resetting the words that were used.
+struct VisitedStates {
+    bits: Vec<u64>,
+    touched: Vec<usize>,
+}
+
+impl VisitedStates {
+    fn new(num_states: usize) -> Self {
+        return VisitedStates {
+            bits: vec![0; num_states.div_ceil(64)],
+            touched: Vec::new(),
+        };
+    }
+
+    // Returns false if the state was already in the set.
+    fn insert(&mut self, state: usize) -> bool {
+        let (word, bit) = (state / 64, 1 << (state % 64));
+        if self.bits[word] & bit != 0 {
+            return false;
+        }
+        if self.bits[word] == 0 {
+            self.touched.push(word);
+        }
+        self.bits[word] |= bit;
+        return true;
+    }
+
+    fn clear(&mut self) {
+        for word in self.touched.drain(..) {
+            self.bits[word] = 0;
+        }
+    }
+}
+
+// For every cell and facing, the cell where the guard stops in front of the next obstacle, or
+// None if the guard walks off the board. Lets a simulation skip straight to the next turn.
+struct JumpTable {
+    width: usize,
+    stops: Vec<Option<u32>>, // Indexed by (y * width + x) * 4 + dir
+}
+
+impl JumpTable {
+    fn new(state: &State) -> Self {
+        let (width, height) = state.board_size;
+        let mut stops: Vec<Option<u32>> = vec![None; width * height * 4];
+        for (dir, (dx, dy)) in DIRS.iter().enumerate() {
+            // Visit cells so the one in front has always been filled in already.
+            let xs: Vec<usize> = if *dx > 0 {
+                (0..width).rev().collect()
+            } else {
+                (0..width).collect()
+            };
+            let ys: Vec<usize> = if *dy > 0 {
+                (0..height).rev().collect()
+            } else {
+                (0..height).collect()
+            };
+            for y in ys.iter() {
+                for x in xs.iter() {
+                    let pos = (*x as i32, *y as i32);
+                    let next_pos = (pos.0 + dx, pos.1 + dy);
+                    let idx = (y * width + x) * 4 + dir;
+                    if !state._is_within_bounds(&next_pos) {
+                        stops[idx] = None;
+                    } else if state.get_cell_at_pos(&next_pos) == Cell::Obstacle {
+                        stops[idx] = Some((y * width + x) as u32);
+                    } else {
+                        let next_idx = (next_pos.1 as usize * width + next_pos.0 as usize) * 4;
+                        stops[idx] = stops[next_idx + dir];
+                    }
+                }
+            }
+        }
+        return JumpTable { width, stops };
+    }
+
+    fn to_pos(&self, cell: u32) -> (i32, i32) {
+        return (
+            (cell as usize % self.width) as i32,
+            (cell as usize / self.width) as i32,
+        );
+    }
+
+    // Where the guard stops walking from pos, taking an extra obstacle into account.
+    fn jump(&self, pos: (i32, i32), dir: usize, obstacle: (i32, i32)) -> Option<(i32, i32)> {
+        let cell = pos.1 as usize * self.width + pos.0 as usize;
+        let stop = self.stops[cell * 4 + dir].map(|c| self.to_pos(c));
+
+        // Steps along the facing direction, the extra obstacle only matters if it's in front.
+        let (dx, dy) = DIRS[dir];
+        let steps_to = |p: (i32, i32)| (p.0 - pos.0) * dx + (p.1 - pos.1) * dy;
+        let on_path = (obstacle.0 - pos.0) * dy == (obstacle.1 - pos.1) * dx;
+        let obstacle_steps = steps_to(obstacle);
+        if on_path && obstacle_steps > 0 && stop.is_none_or(|stop| obstacle_steps <= steps_to(stop))
+        {
+            return Some((obstacle.0 - dx, obstacle.1 - dy));
+        }
+        return stop;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LoopReport {
+    obstacle: (i32, i32),
+    turn_points: Vec<(i32, i32)>, // Where the guard turns, in loop order
+    cycle_length: usize,          // Moves and turns for one trip around the loop
+    entry_step: usize,            // Moves and turns before the guard is on the loop
+}
+
+fn distance(a: (i32, i32), b: (i32, i32)) -> usize {
+    return ((a.0 - b.0).abs() + (a.1 - b.1).abs()) as usize;
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -53,6 +161,8 @@ struct State {
     guard_facing: (i32, i32),                         // (dx, dy)
     guard_pos_history: HashSet<(i32, i32)>,           // (pos_x, pos_y)
     guard_history: HashSet<((i32, i32), (i32, i32))>, // ((pos_x, pos_y), (facing_x, facing_y))
+    guards: Vec<((i32, i32), (i32, i32))>,            // Every guard's starting (pos, facing)
+    teleporters: HashMap<(i32, i32), (i32, i32)>,     // Teleporter cell -> paired cell
 }
 
 impl State {
@@ -67,26 +177,51 @@ impl State {
         let lines = BufReader::new(input_file).lines();
 
         let mut board: Vec<Vec<Cell>> = Vec::new();
-        let mut guard_pos = (0, 0);
-        let guard_facing = (0, -1); // Facing up at start
+        let mut guards: Vec<((i32, i32), (i32, i32))> = Vec::new();
+        let mut teleporter_cells: HashMap<char, Vec<(i32, i32)>> = HashMap::new();
 
         for line in lines {
             let line = line.expect("Could not read line");
             let mut row: Vec<Cell> = Vec::new();
             for c in line.chars() {
+                let pos = (row.len() as i32, board.len() as i32);
                 match c {
                     '.' => row.push(Cell::Unvisited),
                     '#' => row.push(Cell::Obstacle),
-                    '^' => {
-                        guard_pos = (row.len() as i32, board.len() as i32);
+                    '^' | '>' | 'v' | '<' => {
+                        let facing = match c {
+                            '^' => (0, -1),
+                            '>' => (1, 0),
+                            'v' => (0, 1),
+                            _ => (-1, 0),
+                        };
+                        guards.push((pos, facing));
                         row.push(Cell::Guard);
                     }
+                    'a'..='z' => {
+                        teleporter_cells.entry(c).or_default().push(pos);
+                        row.push(Cell::Teleporter(c));
+                    }
                     _ => panic!("Invalid character in input file"),
                 }
             }
             board.push(row);
         }
 
+        let mut teleporters: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+        for (c, cells) in teleporter_cells.iter() {
+            if cells.len() != 2 {
+                panic!(
+                    "Teleporter '{}' needs exactly 2 cells, found {}",
+                    c,
+                    cells.len()
+                );
+            }
+            teleporters.insert(cells[0], cells[1]);
+            teleporters.insert(cells[1], cells[0]);
+        }
+
+        let (guard_pos, guard_facing) = *guards.first().expect("No guard on the board");
         let board_size = (board[0].len(), board.len());
         let guard_pos_history: HashSet<(i32, i32)> = HashSet::new();
         let guard_history: HashSet<((i32, i32), (i32, i32))> = HashSet::new();
@@ -99,6 +234,8 @@ impl State {
             guard_facing,
             guard_pos_history,
             guard_history,
+            guards,
+            teleporters,
         };
     }
 
@@ -127,23 +264,133 @@ impl State {
     }
 
     fn get_board_completion_state(&mut self) -> Completion {
+        let mut steps: u64 = 0;
+        let mut completion = Completion::OutOfBounds;
         while self.has_next_state() {
             if self.is_guard_looping() {
-                return Completion::Loop;
+                completion = Completion::Loop;
+                break;
+            }
+            if cancel::tick() {
+                completion = Completion::Cancelled;
+                break;
             }
             self.next_state();
+            steps += 1;
+        }
+        metrics::add("steps_simulated", steps);
+        return completion;
+    }
+
+    // Simulates the guard from its current state with an extra obstacle, turn to turn.
+    fn completion_with_obstacle(
+        &self,
+        table: &JumpTable,
+        visited: &mut VisitedStates,
+        obstacle: (i32, i32),
+    ) -> Completion {
+        visited.clear();
+        let mut pos = self.guard_pos;
+        let mut dir = dir_index(self.guard_facing);
+        let mut jumps: u64 = 0;
+        let completion = loop {
+            if cancel::tick() {
+                break Completion::Cancelled;
+            }
+            jumps += 1;
+            let Some(stop) = table.jump(pos, dir, obstacle) else {
+                break Completion::OutOfBounds;
+            };
+            let cell = stop.1 as usize * self.board_size.0 + stop.0 as usize;
+            if !visited.insert(cell * 4 + dir) {
+                break Completion::Loop;
+            }
+            pos = stop;
+            dir = (dir + 1) % 4;
+        };
+        metrics::add("jumps", jumps);
+        return completion;
+    }
+
+    // Follows the guard from its current state with an extra obstacle and describes the loop it
+    // ends up in, or None if it leaves the board.
+    fn loop_report(&self, table: &JumpTable, obstacle: (i32, i32)) -> Option<LoopReport> {
+        let mut stops: Vec<((i32, i32), usize)> = Vec::new(); // (pos, facing when arriving)
+        let mut arrival_steps: Vec<usize> = Vec::new();
+        let mut seen: HashMap<((i32, i32), usize), usize> = HashMap::new();
+
+        let mut pos = self.guard_pos;
+        let mut dir = dir_index(self.guard_facing);
+        let mut steps = 0;
+        let first = loop {
+            let stop = table.jump(pos, dir, obstacle)?;
+            steps += distance(pos, stop);
+            if let Some(idx) = seen.get(&(stop, dir)) {
+                break *idx;
+            }
+            seen.insert((stop, dir), stops.len());
+            stops.push((stop, dir));
+            arrival_steps.push(steps);
+            steps += 1; // Turning right
+            pos = stop;
+            dir = (dir + 1) % 4;
+        };
+
+        // The guard joins the loop somewhere on the way to the first repeated turn point, where
+        // its own path and the last leg of the loop start to overlap.
+        let first_pos = stops[first].0;
+        let last_pos = stops.last().unwrap().0;
+        let prev_pos = if first == 0 {
+            self.guard_pos
+        } else {
+            stops[first - 1].0
+        };
+        let overlap = distance(prev_pos, first_pos).min(distance(last_pos, first_pos));
+
+        return Some(LoopReport {
+            obstacle,
+            turn_points: stops[first..].iter().map(|(p, _)| *p).collect(),
+            cycle_length: steps - arrival_steps[first],
+            entry_step: arrival_steps[first] - overlap,
+        });
+    }
+
+    fn render_loop(&self, report: &LoopReport) {
+        let mut board = self.clone();
+        let corners: Vec<&(i32, i32)> = report.turn_points.iter().collect();
+        for i in 0..corners.len() {
+            let (from, to) = (*corners[i], *corners[(i + 1) % corners.len()]);
+            let (dx, dy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+            let mut curr = from;
+            while curr != to {
+                if board.get_cell_at_pos(&curr) != Cell::Guard {
+                    board.set_cell_at_pos(&curr, Cell::Visited);
+                }
+                curr = (curr.0 + dx, curr.1 + dy);
+            }
+        }
+        for corner in corners {
+            board.set_cell_at_pos(corner, Cell::TurnPoint);
         }
-        return Completion::OutOfBounds;
+        board.set_cell_at_pos(&report.obstacle, Cell::AddedObstacle);
+        board.pretty_print_board();
     }
 
     fn get_obstacles_to_force_loop(&mut self) -> Vec<(i32, i32)> {
         // Validate that self always ends in out of bounds
-        if self.clone().get_board_completion_state() != Completion::OutOfBounds {
-            panic!("Initial problem already loops :(");
+        match self.clone().get_board_completion_state() {
+            Completion::OutOfBounds => (),
+            Completion::Loop => panic!("Initial problem already loops :("),
+            Completion::Cancelled => return Vec::new(),
+            Completion::Collision => unreachable!(), // Only one guard
         }
 
+        let table = JumpTable::new(self);
+        let mut visited = VisitedStates::new(self.board_size.0 * self.board_size.1 * 4);
         let mut obstacles: Vec<(i32, i32)> = Vec::new();
-        while self.has_next_state() {
+        let mut steps: u64 = 0;
+        let mut candidate_boards: u64 = 0;
+        while self.has_next_state() && !cancel::is_cancelled() {
             let curr_pos = self.guard_pos;
             let (dx, dy) = self.guard_facing;
             let next_pos = (curr_pos.0 + dx, curr_pos.1 + dy);
@@ -153,16 +400,19 @@ impl State {
             if self.get_cell_at_pos(&next_pos) != Cell::Obstacle
                 && !self.was_guard_at_pos(&next_pos)
             {
-                let mut test_board = self.clone();
-                test_board.set_cell_at_pos(&next_pos, Cell::Obstacle);
-                if test_board.get_board_completion_state() == Completion::Loop {
+                candidate_boards += 1;
+                if self.completion_with_obstacle(&table, &mut visited, next_pos) == Completion::Loop
+                {
                     obstacles.push(next_pos);
                 }
             }
 
             self.next_state();
+            steps += 1;
         }
 
+        metrics::add("steps_simulated", steps);
+        metrics::add("candidate_boards", candidate_boards);
         return obstacles;
     }
 
@@ -231,47 +481,295 @@ impl State {
                     },
                     Cell::Obstacle => print!("#"),
                     Cell::AddedObstacle => print!("O"),
+                    Cell::TurnPoint => print!("+"),
+                    Cell::Teleporter(c) => print!("{}", c),
                 }
             }
             println!();
         }
         println!();
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TurnRule {
+    Right,
+    Left,
+    Alternate, // Right first, then left, then right...
+}
 
-    fn count_visited_cells(&self) -> usize {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PatrolRules {
+    turn: TurnRule,
+    wrap: bool, // Walking off an edge comes back in on the opposite edge
+}
+
+impl PatrolRules {
+    fn from_config() -> Self {
+        let turn: String = config::day_param(6, "turn", "right".to_string());
+        return PatrolRules {
+            turn: match turn.as_str() {
+                "right" => TurnRule::Right,
+                "left" => TurnRule::Left,
+                "alternate" => TurnRule::Alternate,
+                _ => panic!("Unknown turn rule: {}", turn),
+            },
+            wrap: config::day_param(6, "wrap", false),
+        };
+    }
+
+    fn is_default(&self) -> bool {
+        return self.turn == TurnRule::Right && !self.wrap;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Guard {
+    pos: (i32, i32),
+    facing: usize, // Index into DIRS
+    turns: usize,
+    steps: usize, // Moves and turns
+    completion: Option<Completion>,
+}
+
+// Any number of guards patrolling the same board under a set of rules. Guards take turns moving
+// one step at a time and treat each other as obstacles.
+struct Patrol {
+    state: State,
+    rules: PatrolRules,
+    guards: Vec<Guard>,
+    visited: HashSet<(i32, i32)>,
+    seen: HashSet<Vec<((i32, i32), usize, usize)>>,
+}
+
+impl Patrol {
+    fn new(state: State, rules: PatrolRules) -> Self {
+        let guards: Vec<Guard> = state
+            .guards
+            .iter()
+            .map(|(pos, facing)| Guard {
+                pos: *pos,
+                facing: dir_index(*facing),
+                turns: 0,
+                steps: 0,
+                completion: None,
+            })
+            .collect();
+        let visited = guards.iter().map(|g| g.pos).collect();
+        return Patrol {
+            state,
+            rules,
+            guards,
+            visited,
+            seen: HashSet::new(),
+        };
+    }
+
+    fn is_active(&self) -> bool {
+        return self.guards.iter().any(|g| g.completion.is_none());
+    }
+
+    fn guard_at(&self, pos: (i32, i32)) -> Option<usize> {
         return self
-            .board
+            .guards
             .iter()
-            .flatten()
-            .filter(|c| **c == Cell::Visited || **c == Cell::Guard)
-            .count();
+            .position(|g| g.completion.is_none() && g.pos == pos);
     }
-}
 
-fn part1(input_file: &PathBuf) {
-    let mut board_state = State::from_file(input_file);
-    board_state.pretty_print_board();
+    fn turn(&mut self, idx: usize) {
+        let guard = &mut self.guards[idx];
+        let right = match self.rules.turn {
+            TurnRule::Right => true,
+            TurnRule::Left => false,
+            TurnRule::Alternate => guard.turns % 2 == 0,
+        };
+        guard.facing = (guard.facing + if right { 1 } else { 3 }) % 4;
+        guard.turns += 1;
+    }
+
+    fn step_guard(&mut self, idx: usize) {
+        let guard = &self.guards[idx];
+        let (dx, dy) = DIRS[guard.facing];
+        let mut next_pos = (guard.pos.0 + dx, guard.pos.1 + dy);
+        self.guards[idx].steps += 1;
+
+        if !self.state._is_within_bounds(&next_pos) {
+            if !self.rules.wrap {
+                self.guards[idx].completion = Some(Completion::OutOfBounds);
+                return;
+            }
+            let (width, height) = self.state.board_size;
+            next_pos = (
+                next_pos.0.rem_euclid(width as i32),
+                next_pos.1.rem_euclid(height as i32),
+            );
+        }
+
+        if self.state.get_cell_at_pos(&next_pos) == Cell::Obstacle {
+            self.turn(idx);
+            return;
+        }
+        if let Some(other) = self.guard_at(next_pos) {
+            if (self.guards[other].facing + 2) % 4 == self.guards[idx].facing {
+                // Walking straight at each other, neither can get past.
+                self.guards[idx].completion = Some(Completion::Collision);
+                self.guards[other].completion = Some(Completion::Collision);
+            } else {
+                self.turn(idx);
+            }
+            return;
+        }
+
+        self.visited.insert(next_pos);
+        if let Some(target) = self.state.teleporters.get(&next_pos) {
+            next_pos = *target;
+            self.visited.insert(next_pos);
+            if let Some(other) = self.guard_at(next_pos) {
+                self.guards[other].completion = Some(Completion::Collision);
+                self.guards[idx].completion = Some(Completion::Collision);
+            }
+        }
+        self.guards[idx].pos = next_pos;
+    }
+
+    fn next_round(&mut self) {
+        for idx in 0..self.guards.len() {
+            if self.guards[idx].completion.is_none() {
+                self.step_guard(idx);
+            }
+        }
+
+        // With several guards only the whole board repeating means a loop.
+        let snapshot: Vec<((i32, i32), usize, usize)> = self
+            .guards
+            .iter()
+            .filter(|g| g.completion.is_none())
+            .map(|g| (g.pos, g.facing, g.turns % 2))
+            .collect();
+        let is_loop = !self.seen.insert(snapshot);
+        let is_cancelled = cancel::tick();
+        for guard in self.guards.iter_mut().filter(|g| g.completion.is_none()) {
+            if is_loop {
+                guard.completion = Some(Completion::Loop);
+            } else if is_cancelled {
+                guard.completion = Some(Completion::Cancelled);
+            }
+        }
+    }
+
+    fn pretty_print_board(&self) {
+        for (y, row) in self.state.board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let pos = (x as i32, y as i32);
+                if let Some(idx) = self.guard_at(pos) {
+                    print!("{}", ["↑", "→", "↓", "←"][self.guards[idx].facing]);
+                    continue;
+                }
+                match cell {
+                    Cell::Obstacle => print!("#"),
+                    Cell::Teleporter(c) => print!("{}", c),
+                    _ if self.visited.contains(&pos) => print!("*"),
+                    _ => print!("."),
+                }
+            }
+            println!();
+        }
+        println!();
+    }
+}
 
-    while board_state.has_next_state() {
-        board_state.next_state();
-        board_state.pretty_print_board();
+fn part1(input_file: &PathBuf) -> Explanation {
+    let board_state = State::from_file(input_file);
+    let mut patrol = Patrol::new(board_state, PatrolRules::from_config());
+    patrol.pretty_print_board();
+    let mut explanation = Explanation::default();
+    for (i, guard) in patrol.guards.iter().enumerate() {
+        explanation.step(
+            format!("Guard {} starts", i + 1),
+            format!("{:?}", guard.pos),
+        );
     }
 
-    println!("Visited cells: {}", board_state.count_visited_cells());
+    while patrol.is_active() {
+        patrol.next_round();
+        patrol.pretty_print_board();
+    }
+    metrics::add(
+        "steps_simulated",
+        patrol.guards.iter().map(|g| g.steps as u64).sum(),
+    );
+
+    for (i, guard) in patrol.guards.iter().enumerate() {
+        let completion = guard.completion.expect("Guard still patrolling");
+        println!(
+            "Guard {}: {:?} after {} moves and turns",
+            i + 1,
+            completion,
+            guard.steps
+        );
+        explanation.step(
+            format!("Guard {} {:?}", i + 1, completion),
+            format!(
+                "{:?} facing {:?} after {} moves and turns",
+                guard.pos, DIRS[guard.facing], guard.steps
+            ),
+        );
+    }
+    println!("Visited cells: {}", patrol.visited.len());
+    return explanation.answer(patrol.visited.len());
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let vanilla_board = State::from_file(input_file);
+    if !PatrolRules::from_config().is_default()
+        || vanilla_board.guards.len() > 1
+        || !vanilla_board.teleporters.is_empty()
+    {
+        panic!("Part 2 only supports a single guard with the default patrol rules");
+    }
 
     let mut working_board = vanilla_board.clone();
     let obstacles = working_board.get_obstacles_to_force_loop();
 
+    let table = JumpTable::new(&vanilla_board);
+    let mut reports: Vec<LoopReport> = Vec::new();
+    let mut explanation = Explanation::default();
     for obstacle in obstacles.iter() {
         let mut print_board = vanilla_board.clone();
         print_board.set_cell_at_pos(obstacle, Cell::AddedObstacle);
         println!("Obstacle at {:?}", obstacle);
         print_board.pretty_print_board();
+
+        let report = vanilla_board
+            .loop_report(&table, *obstacle)
+            .expect("Obstacle was found to cause a loop");
+        println!(
+            "Loop: turns at {:?}, cycle length {}, entered at step {}",
+            report.turn_points, report.cycle_length, report.entry_step
+        );
+        println!();
+        explanation.step(
+            format!("Obstacle at {:?}", obstacle),
+            format!(
+                "guard loops through {} turns of {} steps, entered at step {}",
+                report.turn_points.len(),
+                report.cycle_length,
+                report.entry_step
+            ),
+        );
+        reports.push(report);
+    }
+
+    // Loops are numbered from 1 in the order the obstacles were found.
+    let render_loop: Option<usize> = config::day_param(6, "render_loop", None);
+    if let Some(loop_num) = render_loop {
+        let report = reports
+            .get(loop_num.wrapping_sub(1))
+            .unwrap_or_else(|| panic!("No loop number {}, there are {}", loop_num, reports.len()));
+        println!("Loop {} for obstacle at {:?}", loop_num, report.obstacle);
+        vanilla_board.render_loop(report);
     }
 
     println!("Possible obstacles for loop: {}", obstacles.len());
+    return explanation.answer(obstacles.len());
 }
@@ -1,39 +1,24 @@
-use std::collections::VecDeque;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter, Result};
 use std::io::BufRead;
 use std::{fs::File, io::BufReader, path::PathBuf};
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day7.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 7, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 7, Part 2");
-        part2(&input);
-        return;
-    }
+use crate::{config, explain::Explanation, metrics, runner};
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 7, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 7, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(7));
+    runner::run_parts(7, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Operator {
     Plus,
     Mutiply,
-    Concat,
+    Minus,
+    Divide, // Integer division, rounding down
+    Power,
+    Xor,
+    Concat(u64), // Appends the digits of the right operand, written in the given base
 }
 
 impl Display for Operator {
@@ -41,11 +26,190 @@ impl Display for Operator {
         match *self {
             Operator::Plus => write!(f, "+"),
             Operator::Mutiply => write!(f, "*"),
-            Operator::Concat => write!(f, "||"),
+            Operator::Minus => write!(f, "-"),
+            Operator::Divide => write!(f, "/"),
+            Operator::Power => write!(f, "^"),
+            Operator::Xor => write!(f, "xor"),
+            Operator::Concat(10) => write!(f, "||"),
+            Operator::Concat(base) => write!(f, "||{}", base),
+        }
+    }
+}
+
+impl Operator {
+    // Names accepted in the operator list, concat can be given a base like "concat2".
+    fn from_name(name: &str) -> Self {
+        let name = name.trim();
+        return match name {
+            "add" => Operator::Plus,
+            "mul" => Operator::Mutiply,
+            "sub" => Operator::Minus,
+            "div" => Operator::Divide,
+            "pow" => Operator::Power,
+            "xor" => Operator::Xor,
+            "concat" => Operator::Concat(10),
+            _ => match name.strip_prefix("concat").map(|b| b.parse::<u64>()) {
+                Some(Ok(base)) if base >= 2 => Operator::Concat(base),
+                _ => panic!("Unknown operator: {}", name),
+            },
+        };
+    }
+
+    // None when the result doesn't fit in a u64, would be negative, or divides by zero.
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        return match *self {
+            Operator::Plus => left.checked_add(right),
+            Operator::Mutiply => left.checked_mul(right),
+            Operator::Minus => left.checked_sub(right),
+            Operator::Divide => left.checked_div(right),
+            Operator::Power => left.checked_pow(u32::try_from(right).ok()?),
+            Operator::Xor => Some(left ^ right),
+            Operator::Concat(base) => {
+                let mut shift = base;
+                while shift <= right {
+                    shift = shift.checked_mul(base)?;
+                }
+                left.checked_mul(shift)?.checked_add(right)
+            }
+        };
+    }
+
+    // The left operand that gives result, if there is exactly one. Undoing || checks the result
+    // ends with the digits of the right operand, undoing * checks the division is exact. Any left
+    // operand gives 0 * 0, the solvers handle that themselves.
+    fn invert(&self, result: u64, right: u64) -> Option<u64> {
+        return match *self {
+            Operator::Plus => result.checked_sub(right),
+            Operator::Mutiply => match right {
+                0 => None,
+                _ if result % right == 0 => Some(result / right),
+                _ => None,
+            },
+            Operator::Minus => result.checked_add(right),
+            Operator::Xor => Some(result ^ right),
+            Operator::Concat(base) => {
+                let mut shift = base;
+                while shift <= right {
+                    shift = shift.checked_mul(base)?;
+                }
+                let prefix = result.checked_sub(right)?;
+                if prefix % shift == 0 {
+                    Some(prefix / shift)
+                } else {
+                    None
+                }
+            }
+            Operator::Divide | Operator::Power => {
+                panic!("Operator {} can't be undone", self)
+            }
+        };
+    }
+
+    // Integer division and powers can come from many left operands, so they can't be searched
+    // backwards.
+    fn is_invertible(&self) -> bool {
+        return !matches!(*self, Operator::Divide | Operator::Power);
+    }
+
+    // Whether the result is never smaller than the left operand. Holds for +, * and || as long
+    // as the right operand isn't 0, see Problem::can_prune.
+    fn is_monotonic(&self) -> bool {
+        return match *self {
+            Operator::Plus | Operator::Mutiply | Operator::Concat(_) => true,
+            Operator::Minus | Operator::Divide | Operator::Power | Operator::Xor => false,
+        };
+    }
+}
+
+fn operators_from_config(part: u32) -> Vec<Operator> {
+    let default = match part {
+        1 => vec!["add", "mul"],
+        _ => vec!["add", "mul", "concat"],
+    };
+    let names: Vec<String> = config::day_param(
+        7,
+        format!("part{}_operators", part).as_str(),
+        default.iter().map(|s| s.to_string()).collect(),
+    );
+    return names.iter().map(|n| Operator::from_name(n)).collect();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Evaluation {
+    LeftToRight,
+    Precedence { concat_tightest: bool }, // Powers, then * and /, then + and -, then xor
+}
+
+impl Evaluation {
+    fn from_config() -> Self {
+        let evaluation: String = config::day_param(7, "evaluation", "left_to_right".to_string());
+        return match evaluation.as_str() {
+            "left_to_right" => Evaluation::LeftToRight,
+            "precedence" => {
+                let concat: String =
+                    config::day_param(7, "concat_precedence", "tightest".to_string());
+                Evaluation::Precedence {
+                    concat_tightest: match concat.as_str() {
+                        "tightest" => true,
+                        "loosest" => false,
+                        _ => panic!("Unknown concat precedence: {}", concat),
+                    },
+                }
+            }
+            _ => panic!("Unknown evaluation: {}", evaluation),
+        };
+    }
+
+    // Higher binds tighter, operators of the same precedence are evaluated left to right.
+    fn precedence(&self, operator: &Operator) -> i32 {
+        let Evaluation::Precedence { concat_tightest } = self else {
+            return 0;
+        };
+        return match *operator {
+            Operator::Concat(_) if *concat_tightest => 4,
+            Operator::Concat(_) => -1,
+            Operator::Power => 3,
+            Operator::Mutiply | Operator::Divide => 2,
+            Operator::Plus | Operator::Minus => 1,
+            Operator::Xor => 0,
+        };
+    }
+
+    // The stack holds values with the operator in front of each one. Everything that binds at
+    // least as tight as the new operator is evaluated before it is pushed, so the precedence only
+    // ever goes up towards the top of the stack. Returns None if an operator fails.
+    fn push(
+        &self,
+        stack: &mut Vec<(u64, Option<Operator>)>,
+        operator: Operator,
+        operand: u64,
+    ) -> Option<()> {
+        while let Some(top) = stack.last().and_then(|(_, op)| *op) {
+            if self.precedence(&top) < self.precedence(&operator) {
+                break;
+            }
+            let (right, _) = stack.pop().unwrap();
+            let (left, left_op) = stack.pop().unwrap();
+            stack.push((top.apply(left, right)?, left_op));
+        }
+        stack.push((operand, Some(operator)));
+        return Some(());
+    }
+
+    fn finish(&self, mut stack: Vec<(u64, Option<Operator>)>) -> Option<u64> {
+        while stack.len() > 1 {
+            let (right, op) = stack.pop().unwrap();
+            let (left, left_op) = stack.pop().unwrap();
+            stack.push((op.unwrap().apply(left, right)?, left_op));
         }
+        return stack.pop().map(|(value, _)| value);
     }
 }
 
+// Reachable values, with how many operator sequences reach each and one (previous value, operator)
+// step that does.
+type Layer = HashMap<u64, (u64, Option<(u64, Operator)>)>;
+
 #[derive(Debug, Clone)]
 struct Problem {
     target: u64,
@@ -67,87 +231,339 @@ impl Problem {
         return Self { target, operands };
     }
 
-    fn get_possible_solution_operators(&self, check_concat: bool) -> Vec<Vec<Operator>> {
-        // There is an assumption that the values never get smaller
-        // after an operation. Given that we're working with +, *, and ||, the
-        // only situation this assumption might fail is with 'some number * 0'.
-        // All other operations will always result in larger (or equal) number.
-        // Manually verified that 0 is never an operand in the input :P
-        let mut working_operands: VecDeque<(u64, Vec<Operator>)> = VecDeque::new();
-        working_operands.push_back((self.operands[0], Vec::new()));
+    // Values that overshoot the target can be dropped only if no operator can ever make them
+    // smaller again. Multiplying by a 0 operand does, whatever the operators are.
+    fn can_prune(&self, operators: &Vec<Operator>) -> bool {
+        return operators.iter().all(|op| op.is_monotonic()) && !self.operands.contains(&0);
+    }
 
-        for operand in self.operands.iter().skip(1) {
-            let working_operands_count = working_operands.len();
+    // Forward search, one layer of reachable values per operand. Each value keeps the number of
+    // operator sequences reaching it and the last step of one of them, so a value reached in many
+    // ways is only expanded once. With a target, the last layer only keeps that value.
+    fn forward_layers(
+        &self,
+        len: usize,
+        operators: &Vec<Operator>,
+        target: Option<u64>,
+    ) -> Vec<Layer> {
+        let can_prune = target.is_some() && self.can_prune(operators);
+        let mut layers: Vec<Layer> = vec![HashMap::from([(self.operands[0], (1, None))])];
+        let mut states_expanded: u64 = 0;
+        for (idx, operand) in self.operands.iter().enumerate().take(len).skip(1) {
+            let mut next: Layer = HashMap::new();
+            for (value, (ways, _)) in layers.last().unwrap().iter() {
+                states_expanded += 1;
+                for operator in operators.iter() {
+                    let Some(next_value) = operator.apply(*value, *operand) else {
+                        continue;
+                    };
+                    if can_prune && next_value > self.target {
+                        continue;
+                    }
+                    if idx == len - 1 && target.is_some_and(|t| t != next_value) {
+                        continue;
+                    }
+                    let entry = next.entry(next_value).or_insert((0, None));
+                    entry.0 += ways;
+                    // Keep the smallest step, so the solution shown doesn't depend on hash order
+                    if entry.1.is_none_or(|(prev, _)| *value < prev) {
+                        entry.1 = Some((*value, *operator));
+                    }
+                }
+            }
+            layers.push(next);
+        }
+        metrics::add("states_expanded", states_expanded);
+        return layers;
+    }
 
-            if working_operands_count == 0 {
-                break;
+    // One operator sequence reaching value in the last layer, following the recorded steps back.
+    fn walk_back(layers: &Vec<Layer>, value: u64) -> Vec<Operator> {
+        let mut solution: Vec<Operator> = Vec::new();
+        let mut value = value;
+        for layer in layers.iter().skip(1).rev() {
+            let (_, Some((prev, operator))) = layer[&value] else {
+                unreachable!("Only the first layer has no previous step");
+            };
+            solution.push(operator);
+            value = prev;
+        }
+        solution.reverse();
+        return solution;
+    }
+
+    // The first solution found and the number of solutions.
+    fn forward_solution(&self, operators: &Vec<Operator>) -> (Option<Vec<Operator>>, u64) {
+        let layers = self.forward_layers(self.operands.len(), operators, Some(self.target));
+        return match layers.last().unwrap().get(&self.target) {
+            Some((ways, _)) => (Some(Problem::walk_back(&layers, self.target)), *ways),
+            None => (None, 0),
+        };
+    }
+
+    // Works back from the target, undoing one operator per operand from the right. Every branch
+    // that can't be undone is dropped straight away, which prunes far more than searching forward.
+    fn count_solutions(&self, operators: &Vec<Operator>) -> u64 {
+        return self.count_solutions_from(self.operands.len() - 1, self.target, operators);
+    }
+
+    fn count_solutions_from(&self, idx: usize, target: u64, operators: &Vec<Operator>) -> u64 {
+        metrics::incr("states_expanded");
+        if idx == 0 {
+            return (target == self.operands[0]) as u64;
+        }
+        let mut num_solutions = 0;
+        for op in operators.iter() {
+            if self.is_any_times_zero(op, idx, target) {
+                num_solutions += self.count_evaluations(idx, operators);
+            } else if let Some(prev) = op.invert(target, self.operands[idx]) {
+                num_solutions += self.count_solutions_from(idx - 1, prev, operators);
             }
+        }
+        return num_solutions;
+    }
 
-            for _ in 0..working_operands_count {
-                let (current_value, current_operands) = working_operands.pop_front().unwrap();
+    // Whether op with the operand at idx gives target whatever the operands before it evaluate to.
+    fn is_any_times_zero(&self, op: &Operator, idx: usize, target: u64) -> bool {
+        return *op == Operator::Mutiply && self.operands[idx] == 0 && target == 0;
+    }
 
-                // Check if + is a valid option
-                let next_operand = current_value + operand;
-                if next_operand <= self.target {
-                    let mut next_operators = current_operands.clone();
-                    next_operators.push(Operator::Plus);
-                    working_operands.push_back((next_operand, next_operators));
-                }
+    // Number of operator sequences over the first len operands that evaluate without failing.
+    fn count_evaluations(&self, len: usize, operators: &Vec<Operator>) -> u64 {
+        let layers = self.forward_layers(len, operators, None);
+        return layers.last().unwrap().values().map(|(ways, _)| ways).sum();
+    }
 
-                // Check if * is a valid option
-                let next_operand = current_value * operand;
-                if next_operand <= self.target {
-                    let mut next_operators = current_operands.clone();
-                    next_operators.push(Operator::Mutiply);
-                    working_operands.push_back((next_operand, next_operators));
-                }
+    fn first_solution(&self, operators: &Vec<Operator>) -> Option<Vec<Operator>> {
+        let mut solution: Vec<Operator> = Vec::new();
+        if !self.first_solution_from(
+            self.operands.len() - 1,
+            self.target,
+            operators,
+            &mut solution,
+        ) {
+            return None;
+        }
+        solution.reverse(); // Operators were found right to left
+        return Some(solution);
+    }
 
-                if check_concat {
-                    // Check if || is a valid option
-                    let next_operand = format!("{}{}", current_value, operand);
-                    let next_operand = next_operand.parse::<u64>().unwrap();
-                    if next_operand <= self.target {
-                        let mut next_operators = current_operands.clone();
-                        next_operators.push(Operator::Concat);
-                        working_operands.push_back((next_operand, next_operators));
-                    }
-                }
+    fn first_solution_from(
+        &self,
+        idx: usize,
+        target: u64,
+        operators: &Vec<Operator>,
+        solution: &mut Vec<Operator>,
+    ) -> bool {
+        metrics::incr("states_expanded");
+        if idx == 0 {
+            return target == self.operands[0];
+        }
+        for op in operators.iter() {
+            if self.is_any_times_zero(op, idx, target) {
+                let layers = self.forward_layers(idx, operators, None);
+                let Some(value) = layers.last().unwrap().keys().min() else {
+                    continue;
+                };
+                solution.push(*op);
+                solution.extend(Problem::walk_back(&layers, *value).iter().rev());
+                return true;
+            }
+            let Some(prev) = op.invert(target, self.operands[idx]) else {
+                continue;
+            };
+            solution.push(*op);
+            if self.first_solution_from(idx - 1, prev, operators, solution) {
+                return true;
             }
+            solution.pop();
         }
+        return false;
+    }
 
-        return working_operands
-            .into_iter()
-            .filter(|v| v.0 == self.target)
-            .map(|v| v.1)
-            .collect();
+    // Tries every operator sequence, evaluating each with operator precedence. Partial values are
+    // kept on a stack, see Evaluation::push.
+    fn get_precedence_solutions(
+        &self,
+        operators: &Vec<Operator>,
+        evaluation: &Evaluation,
+    ) -> Vec<Vec<Operator>> {
+        let mut solutions: Vec<Vec<Operator>> = Vec::new();
+        let mut stack = vec![(self.operands[0], None)];
+        self.search_with_precedence(
+            1,
+            &mut stack,
+            &mut Vec::new(),
+            operators,
+            evaluation,
+            &mut solutions,
+        );
+        return solutions;
+    }
+
+    fn search_with_precedence(
+        &self,
+        idx: usize,
+        stack: &mut Vec<(u64, Option<Operator>)>,
+        current: &mut Vec<Operator>,
+        operators: &Vec<Operator>,
+        evaluation: &Evaluation,
+        solutions: &mut Vec<Vec<Operator>>,
+    ) {
+        metrics::incr("states_expanded");
+        // When nothing on the stack can shrink, anything over the target is a dead end.
+        if self.can_prune(operators) && stack.iter().any(|(value, _)| *value > self.target) {
+            return;
+        }
+        if idx == self.operands.len() {
+            if evaluation.finish(stack.clone()) == Some(self.target) {
+                solutions.push(current.clone());
+            }
+            return;
+        }
+
+        for op in operators.iter() {
+            let mut next_stack = stack.clone();
+            if evaluation
+                .push(&mut next_stack, *op, self.operands[idx])
+                .is_none()
+            {
+                continue;
+            }
+            current.push(*op);
+            self.search_with_precedence(
+                idx + 1,
+                &mut next_stack,
+                current,
+                operators,
+                evaluation,
+                solutions,
+            );
+            current.pop();
+        }
+    }
+
+    // Fully parenthesized, so it reads the same whichever evaluation order is used.
+    fn solution_to_string(&self, operators: &Vec<Operator>, evaluation: &Evaluation) -> String {
+        let mut stack: Vec<(String, Option<Operator>)> = vec![(self.operands[0].to_string(), None)];
+        for (operator, operand) in operators.iter().zip(self.operands.iter().skip(1)) {
+            while let Some(top) = stack.last().and_then(|(_, op)| *op) {
+                if evaluation.precedence(&top) < evaluation.precedence(operator) {
+                    break;
+                }
+                let (right, _) = stack.pop().unwrap();
+                let (left, left_op) = stack.pop().unwrap();
+                stack.push((format!("({} {} {})", left, top, right), left_op));
+            }
+            stack.push((operand.to_string(), Some(*operator)));
+        }
+        while stack.len() > 1 {
+            let (right, op) = stack.pop().unwrap();
+            let (left, left_op) = stack.pop().unwrap();
+            stack.push((format!("({} {} {})", left, op.unwrap(), right), left_op));
+        }
+        return stack.pop().unwrap().0;
     }
 
-    fn pretty_print_solution(&self, operators: &Vec<Operator>) {
+    fn pretty_print_solution(&self, operators: &Vec<Operator>, evaluation: &Evaluation) {
         if operators.len() != self.operands.len() - 1 {
             panic!("Length of operators should be one less than length of operators");
         }
 
-        print!("    {} = ", self.target);
-        for (operand, operator) in self.operands.iter().zip(operators.iter()) {
-            print!("{} {} ", operand, operator);
-        }
-        println!("{}", self.operands.last().unwrap());
+        println!(
+            "    {} = {}",
+            self.target,
+            self.solution_to_string(operators, evaluation)
+        );
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let problems = get_problems_from_file(input_file);
+    return solve_problems(&problems, &operators_from_config(1));
+}
+
+fn solve_problems(problems: &Vec<Problem>, operators: &Vec<Operator>) -> Explanation {
+    let first_only: bool = config::day_param(7, "first_only", false);
+    let cross_check: bool = config::day_param(7, "cross_check", false);
+    let evaluation = Evaluation::from_config();
+    let mut solver: String = config::day_param(7, "solver", "backward".to_string());
+    if evaluation != Evaluation::LeftToRight {
+        solver = "precedence".to_string(); // Both other solvers rely on left to right order
+    } else if solver == "backward" && !operators.iter().all(|op| op.is_invertible()) {
+        println!("Some operators can't be undone, using the forward solver");
+        solver = "forward".to_string();
+    }
 
+    let mut explanation = Explanation::default();
     let mut num_solved_problems = 0;
     let mut solved_problems_sum = 0;
     println!("Solved Problems:");
     for problem in problems.iter() {
-        let solutions = problem.get_possible_solution_operators(/* check_concat: */ false);
+        let (solutions, num_solutions) = match solver.as_str() {
+            "forward" => {
+                let (solution, num_solutions) = problem.forward_solution(operators);
+                let solutions: Vec<Vec<Operator>> = solution.into_iter().collect();
+                let num_solutions = if first_only {
+                    solutions.len() as u64
+                } else {
+                    num_solutions
+                };
+                (solutions, num_solutions)
+            }
+            "precedence" => {
+                let mut solutions = problem.get_precedence_solutions(operators, &evaluation);
+                let num_solutions = solutions.len() as u64;
+                if first_only {
+                    solutions.truncate(1);
+                }
+                (solutions, num_solutions)
+            }
+            "backward" => {
+                let solutions: Vec<Vec<Operator>> =
+                    problem.first_solution(operators).into_iter().collect();
+                let num_solutions = if first_only {
+                    solutions.len() as u64
+                } else {
+                    problem.count_solutions(operators)
+                };
+                (solutions, num_solutions)
+            }
+            _ => panic!("Unknown solver: {}", solver),
+        };
+
+        if cross_check && solver == "backward" && !first_only {
+            let (_, forward_solutions) = problem.forward_solution(operators);
+            if forward_solutions != num_solutions {
+                panic!(
+                    "Backward solver disagrees with forward solver for {:?}: {} vs {}",
+                    problem, num_solutions, forward_solutions
+                );
+            }
+        }
 
         for solution in solutions.iter() {
-            problem.pretty_print_solution(solution);
+            problem.pretty_print_solution(solution, &evaluation);
         }
-        if solutions.len() > 0 {
+        if num_solutions > solutions.len() as u64 {
+            println!("    ({} solutions in total)", num_solutions);
+        }
+        if let Some(solution) = solutions.first() {
+            let count = if first_only {
+                "first solution".to_string()
+            } else {
+                format!("{} solutions", num_solutions)
+            };
+            explanation.step(
+                problem.target,
+                format!(
+                    "{} ({})",
+                    problem.solution_to_string(solution, &evaluation),
+                    count
+                ),
+            );
+        }
+        if !solutions.is_empty() {
             num_solved_problems += 1;
             solved_problems_sum += problem.target;
             println!();
@@ -156,6 +572,7 @@ fn part1(input_file: &PathBuf) {
 
     println!("Number of solved problems: {}", num_solved_problems);
     println!("Sum of solved problems: {}", solved_problems_sum);
+    return explanation.answer(solved_problems_sum);
 }
 
 fn get_problems_from_file(input_file: &PathBuf) -> Vec<Problem> {
@@ -177,25 +594,7 @@ fn get_problems_from_file(input_file: &PathBuf) -> Vec<Problem> {
     return problems;
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let problems = get_problems_from_file(input_file);
-
-    let mut num_solved_problems = 0;
-    let mut solved_problems_sum = 0;
-    println!("Solved Problems:");
-    for problem in problems.iter() {
-        let solutions = problem.get_possible_solution_operators(/* check_concat: */ true);
-
-        for solution in solutions.iter() {
-            problem.pretty_print_solution(solution);
-        }
-        if solutions.len() > 0 {
-            num_solved_problems += 1;
-            solved_problems_sum += problem.target;
-            println!();
-        }
-    }
-
-    println!("Number of solved problems: {}", num_solved_problems);
-    println!("Sum of solved problems: {}", solved_problems_sum);
+    return solve_problems(&problems, &operators_from_config(2));
 }
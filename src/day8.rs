@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
     path::PathBuf,
@@ -8,30 +8,11 @@ use std::{
 use colored::Colorize;
 use num_rational::Ratio;
 
-pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day8.txt"));
+use crate::{config, explain::Explanation, metrics, runner};
 
-    if part_opt.is_none() {
-        println!("Running Day 8, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 8, Part 2");
-        part2(&input);
-        return;
-    }
-
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 8, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 8, Part 2");
-            part2(&input)
-        }
-        _ => panic!("Invalid Part :("),
-    }
+pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
+    let input = input_opt.unwrap_or(config::default_input(8));
+    runner::run_parts(8, part_opt, || part1(&input), || part2(&input));
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -82,6 +63,22 @@ impl std::ops::Div<i32> for Distance {
     }
 }
 
+impl std::ops::Mul<Ratio<i32>> for Distance {
+    type Output = Result<Distance, ()>;
+
+    fn mul(self, rhs: Ratio<i32>) -> Self::Output {
+        let dx = rhs * self.dx;
+        let dy = rhs * self.dy;
+        if !dx.is_integer() || !dy.is_integer() {
+            return Err(());
+        }
+        return Ok(Distance {
+            dx: dx.to_integer(),
+            dy: dy.to_integer(),
+        });
+    }
+}
+
 impl std::ops::Add<Distance> for Distance {
     type Output = Distance;
     fn add(self, rhs: Self) -> Self::Output {
@@ -103,6 +100,81 @@ impl std::ops::Sub<Distance> for Distance {
     }
 }
 
+// An antinode for a pair of antennas sits on their line, p/q times as far from one antenna as
+// from the other. Part 1 is 2:1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HarmonicRatio {
+    p: i32,
+    q: i32,
+}
+
+impl HarmonicRatio {
+    fn parse(s: &str) -> Self {
+        let (p, q) = s
+            .split_once(":")
+            .expect(format!("Invalid ratio '{}', expected p:q", s).as_str());
+        let p = p
+            .trim()
+            .parse::<i32>()
+            .expect(format!("Invalid ratio '{}'", s).as_str());
+        let q = q
+            .trim()
+            .parse::<i32>()
+            .expect(format!("Invalid ratio '{}'", s).as_str());
+        if p <= 0 || q <= 0 {
+            panic!("Ratio parts should be positive: {}", s);
+        }
+        return HarmonicRatio { p, q };
+    }
+
+    fn from_config() -> Vec<Self> {
+        let ratios: Vec<String> = config::day_param(8, "ratios", vec!["2:1".to_string()]);
+        return ratios.iter().map(|r| HarmonicRatio::parse(r)).collect();
+    }
+
+    // Fractions of the way from the first antenna to the second where the antinode is p/q times
+    // as far from the first. One point between the antennas, and one beyond the second unless
+    // p == q.
+    fn fractions(&self) -> Vec<Ratio<i32>> {
+        let mut ret = vec![Ratio::new(self.p, self.p + self.q)];
+        if self.p != self.q {
+            ret.push(Ratio::new(self.p, self.p - self.q));
+        }
+        return ret;
+    }
+}
+
+impl std::fmt::Display for HarmonicRatio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}:{}", self.p, self.q);
+    }
+}
+
+// Antinodes produced by each frequency, on the board only.
+type AntinodeMap = BTreeMap<char, HashSet<(i32, i32)>>;
+
+fn all_antinodes(by_frequency: &AntinodeMap) -> HashSet<(i32, i32)> {
+    return by_frequency.values().flatten().copied().collect();
+}
+
+// Cells with antinodes from more than one frequency, and which frequencies those are.
+fn interference(by_frequency: &AntinodeMap) -> BTreeMap<(i32, i32), Vec<char>> {
+    let mut frequencies_at: BTreeMap<(i32, i32), Vec<char>> = BTreeMap::new();
+    for (frequency, antinodes) in by_frequency.iter() {
+        for pos in antinodes.iter() {
+            frequencies_at.entry(*pos).or_default().push(*frequency);
+        }
+    }
+    frequencies_at.retain(|_, frequencies| frequencies.len() > 1);
+    return frequencies_at;
+}
+
+// Frequencies picked with day8.freq (or --freq), all of them when it's not set.
+fn frequency_filter() -> Option<HashSet<char>> {
+    let freq: Option<String> = config::day_param(8, "freq", None);
+    return freq.map(|f| f.chars().collect());
+}
+
 #[derive(Debug, Clone)]
 struct State {
     // All positions are tracked as (x, y) where (0, 0) is the top-left corner of the board
@@ -146,27 +218,27 @@ impl State {
         };
     }
 
-    fn get_all_single_harmonic_antinodes(&self) -> HashSet<(i32, i32)> {
-        let mut antinodes: Vec<HashSet<(i32, i32)>> = Vec::new();
-        for (_, positions) in self.antennas.iter() {
+    fn get_all_ratio_antinodes(&self, ratios: &Vec<HarmonicRatio>) -> AntinodeMap {
+        let mut antinodes: AntinodeMap = BTreeMap::new();
+        for (frequency, positions) in self.antennas.iter() {
             if positions.len() < 2 {
                 continue;
             }
 
-            let frequency_antinodes =
-                self.get_single_harmonic_antinodes_for_one_frequency(positions);
-            antinodes.push(frequency_antinodes);
+            let frequency_antinodes = self
+                .get_ratio_antinodes_for_one_frequency(positions, ratios)
+                .into_iter()
+                .filter(|p| self.is_pos_within_board(p))
+                .collect();
+            antinodes.insert(*frequency, frequency_antinodes);
         }
-        return antinodes
-            .into_iter()
-            .flatten()
-            .filter(|p| self.is_pos_within_board(p))
-            .collect();
+        return antinodes;
     }
 
-    fn get_single_harmonic_antinodes_for_one_frequency(
+    fn get_ratio_antinodes_for_one_frequency(
         &self,
         positions: &Vec<(i32, i32)>,
+        ratios: &Vec<HarmonicRatio>,
     ) -> HashSet<(i32, i32)> {
         let mut antinode_positions: HashSet<(i32, i32)> = HashSet::new();
 
@@ -174,6 +246,7 @@ impl State {
             let p1 = positions[i];
             for j in i + 1..positions.len() {
                 let p2 = positions[j];
+                metrics::incr("antenna_pairs");
                 let antennas_in_line = self.get_all_antennas_in_line(p1, p2, positions);
                 if antennas_in_line.len() > 2 {
                     println!(
@@ -185,41 +258,39 @@ impl State {
                     continue;
                 }
 
-                let distance = Distance::between_points(&p1, &p2);
-
-                // Antinodes between antennas
-                let third_distance = distance / 3;
-                if third_distance.is_ok() {
-                    let third_distance = third_distance.unwrap();
-                    antinode_positions.insert(third_distance.point_at_distance(&p1));
-                    antinode_positions.insert((third_distance * -1).point_at_distance(&p2));
+                // Each ratio works both ways round, so measure from either antenna.
+                for (from, to) in [(p1, p2), (p2, p1)] {
+                    let distance = Distance::between_points(&from, &to);
+                    for ratio in ratios.iter() {
+                        for fraction in ratio.fractions() {
+                            // Only whole cells can hold an antinode.
+                            if let Ok(offset) = distance * fraction {
+                                antinode_positions.insert(offset.point_at_distance(&from));
+                            }
+                        }
+                    }
                 }
-
-                // Antinodes outside antennas
-                antinode_positions.insert(distance.point_at_distance(&p2));
-                antinode_positions.insert((distance * -1).point_at_distance(&p1));
             }
         }
 
         return antinode_positions;
     }
 
-    fn get_all_mutli_harmonic_antinodes(&self) -> HashSet<(i32, i32)> {
-        let mut antinodes: Vec<HashSet<(i32, i32)>> = Vec::new();
-        for (_, positions) in self.antennas.iter() {
+    fn get_all_mutli_harmonic_antinodes(&self) -> AntinodeMap {
+        let mut antinodes: AntinodeMap = BTreeMap::new();
+        for (frequency, positions) in self.antennas.iter() {
             if positions.len() < 2 {
                 continue;
             }
 
-            let frequency_antinodes =
-                self.get_multi_harmonic_antinodes_for_one_frequency(positions);
-            antinodes.push(frequency_antinodes);
+            let frequency_antinodes = self
+                .get_multi_harmonic_antinodes_for_one_frequency(positions)
+                .into_iter()
+                .filter(|p| self.is_pos_within_board(p))
+                .collect();
+            antinodes.insert(*frequency, frequency_antinodes);
         }
-        return antinodes
-            .into_iter()
-            .flatten()
-            .filter(|p| self.is_pos_within_board(p))
-            .collect();
+        return antinodes;
     }
 
     fn get_multi_harmonic_antinodes_for_one_frequency(
@@ -232,6 +303,7 @@ impl State {
             let p1 = positions[i];
             for j in i + 1..positions.len() {
                 let p2 = positions[j];
+                metrics::incr("antenna_pairs");
                 let antennas_in_line = self.get_all_antennas_in_line(p1, p2, positions);
                 if antennas_in_line.len() > 2 {
                     println!(
@@ -293,13 +365,89 @@ impl State {
         return ret;
     }
 
+    fn explain_antinodes(&self, by_frequency: &AntinodeMap) -> Explanation {
+        let mut explanation = Explanation::default();
+        let mut frequencies: Vec<&char> = self.antennas.keys().collect();
+        frequencies.sort();
+        for frequency in frequencies {
+            let num_antinodes = by_frequency.get(frequency).map_or(0, |a| a.len());
+            explanation.step(
+                format!("Frequency '{}'", frequency),
+                format!(
+                    "antennas at {:?}, {} antinodes",
+                    self.antennas[frequency], num_antinodes
+                ),
+            );
+        }
+        for (pos, frequencies) in interference(by_frequency) {
+            explanation.step(
+                format!("Interference at {:?}", pos),
+                format!("frequencies {:?}", frequencies),
+            );
+        }
+        return explanation.answer(all_antinodes(by_frequency).len());
+    }
+
+    fn antenna_at(&self, pos: &(i32, i32)) -> Option<char> {
+        return self
+            .antennas
+            .iter()
+            .find(|(_, positions)| positions.contains(pos))
+            .map(|(frequency, _)| *frequency);
+    }
+
+    fn report_antinodes(&self, by_frequency: &AntinodeMap) {
+        println!("Antinodes by frequency:");
+        for (frequency, antinodes) in by_frequency.iter() {
+            let on_antennas = antinodes
+                .iter()
+                .filter(|p| self.antenna_at(p).is_some())
+                .count();
+            println!(
+                "  '{}': {} antennas, {} antinodes, {} on antennas",
+                frequency,
+                self.antennas[frequency].len(),
+                antinodes.len(),
+                on_antennas
+            );
+        }
+
+        let interference = interference(by_frequency);
+        println!("Interference in {} cells:", interference.len());
+        for (pos, frequencies) in interference.iter() {
+            println!("  {:?}: {:?}", pos, frequencies);
+        }
+
+        let mut on_antennas: Vec<((i32, i32), char)> = all_antinodes(by_frequency)
+            .iter()
+            .filter_map(|p| self.antenna_at(p).map(|f| (*p, f)))
+            .collect();
+        on_antennas.sort();
+        println!("Antinodes on antennas: {}", on_antennas.len());
+        for (pos, frequency) in on_antennas.iter() {
+            println!("  {:?}: antenna '{}'", pos, frequency);
+        }
+        println!();
+    }
+
     fn is_pos_within_board(&self, pos: &(i32, i32)) -> bool {
         return pos.0 >= 0 && pos.0 < self.board_size.0 && pos.1 >= 0 && pos.1 < self.board_size.1;
     }
 
-    fn visualize_antinodes(&self, antinodes: &HashSet<(i32, i32)>) {
+    fn visualize_antinodes(&self, by_frequency: &AntinodeMap) {
+        let filter = frequency_filter();
+        let shown = |frequency: &char| filter.as_ref().is_none_or(|f| f.contains(frequency));
+
+        let shown_antinodes: AntinodeMap = by_frequency
+            .iter()
+            .filter(|(frequency, _)| shown(frequency))
+            .map(|(frequency, antinodes)| (*frequency, antinodes.clone()))
+            .collect();
+        let antinodes = all_antinodes(&shown_antinodes);
+        let interference = interference(&shown_antinodes);
+
         let mut board = vec![vec!['.'; self.board_size.0 as usize]; self.board_size.1 as usize];
-        for (frequency, pos) in self.antennas.iter() {
+        for (frequency, pos) in self.antennas.iter().filter(|(f, _)| shown(f)) {
             for (x, y) in pos.iter() {
                 board[*y as usize][*x as usize] = *frequency;
             }
@@ -311,9 +459,17 @@ impl State {
             }
         }
 
+        if let Some(filter) = filter.as_ref() {
+            let mut frequencies: Vec<&char> = filter.iter().collect();
+            frequencies.sort();
+            println!("Showing frequencies: {:?}", frequencies);
+        }
         for (y, row) in board.iter().enumerate() {
             for (x, cell) in row.iter().enumerate() {
-                if antinodes.contains(&(x as i32, y as i32)) {
+                let pos = (x as i32, y as i32);
+                if interference.contains_key(&pos) {
+                    print!("{}", format!("{}", cell).as_str().yellow().bold());
+                } else if antinodes.contains(&pos) {
                     print!("{}", format!("{}", cell).as_str().green());
                 } else {
                     print!("{}", cell);
@@ -326,26 +482,41 @@ impl State {
     }
 }
 
-fn part1(input_file: &PathBuf) {
+fn part1(input_file: &PathBuf) -> Explanation {
     let state = State::from_file(input_file);
-    let antinodes = state.get_all_single_harmonic_antinodes();
+    let ratios = HarmonicRatio::from_config();
+    println!(
+        "Antinode ratios: {}",
+        ratios
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let by_frequency = state.get_all_ratio_antinodes(&ratios);
+    let antinodes = all_antinodes(&by_frequency);
     println!("{:?}", state);
 
-    state.visualize_antinodes(&antinodes);
+    state.report_antinodes(&by_frequency);
+    state.visualize_antinodes(&by_frequency);
     println!(
         "Number of antinodes: {}",
         format!("{}", antinodes.len()).as_str().green().bold()
     );
+    return state.explain_antinodes(&by_frequency);
 }
 
-fn part2(input_file: &PathBuf) {
+fn part2(input_file: &PathBuf) -> Explanation {
     let state = State::from_file(input_file);
-    let antinodes = state.get_all_mutli_harmonic_antinodes();
+    let by_frequency = state.get_all_mutli_harmonic_antinodes();
+    let antinodes = all_antinodes(&by_frequency);
     println!("{:?}", state);
 
-    state.visualize_antinodes(&antinodes);
+    state.report_antinodes(&by_frequency);
+    state.visualize_antinodes(&by_frequency);
     println!(
         "Number of antinodes: {}",
         format!("{}", antinodes.len()).as_str().green().bold()
     );
+    return state.explain_antinodes(&by_frequency);
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{BufRead, BufReader},
     path::PathBuf,
@@ -6,169 +7,439 @@ use std::{
 
 use colored::Colorize;
 
+use crate::{config, explain::Explanation, metrics, runner};
+
 pub fn main(part_opt: Option<u32>, input_opt: Option<PathBuf>) {
-    let input = input_opt.unwrap_or(PathBuf::from("input/day9.txt"));
-
-    if part_opt.is_none() {
-        println!("Running Day 9, Part 1");
-        part1(&input);
-        println!();
-        println!("Running Day 9, Part 2");
-        part2(&input);
-        return;
+    let input = input_opt.unwrap_or(config::default_input(9));
+    runner::run_parts(9, part_opt, || part1(&input), || part2(&input));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    PerBlock,     // Move single blocks from the end into the first free block
+    FirstFit,     // Move whole files, highest id first, into the leftmost gap that fits
+    BestFit,      // Like first fit, but into the smallest gap that fits
+    WorstFit,     // Like first fit, but into the largest gap that fits
+    LargestFirst, // Like first fit, but the largest files go first and get the early gaps
+    FewestMoves,  // Packs the disk like per block, with as few moves as possible
+}
+
+static ALL_STRATEGIES: [Strategy; 6] = [
+    Strategy::PerBlock,
+    Strategy::FirstFit,
+    Strategy::BestFit,
+    Strategy::WorstFit,
+    Strategy::LargestFirst,
+    Strategy::FewestMoves,
+];
+
+// Most files and gaps the fewest moves search handles once equal sizes are paired up, it tries
+// every subset of them.
+static MAX_FEWEST_MOVES_SEARCH: usize = 20;
+
+impl Strategy {
+    fn from_str(s: &str) -> Self {
+        return match s.trim().to_lowercase().as_str() {
+            "per_block" => Strategy::PerBlock,
+            "first_fit" => Strategy::FirstFit,
+            "best_fit" => Strategy::BestFit,
+            "worst_fit" => Strategy::WorstFit,
+            "largest_first" => Strategy::LargestFirst,
+            "fewest_moves" => Strategy::FewestMoves,
+            _ => panic!("Unknown compaction strategy: {}", s),
+        };
     }
 
-    let part = part_opt.unwrap();
-    match part {
-        1 => {
-            println!("Running Day 9, Part 1");
-            part1(&input)
-        }
-        2 => {
-            println!("Running Day 9, Part 2");
-            part2(&input)
+    fn parse_list(s: &str) -> Vec<Self> {
+        if s.trim() == "all" {
+            return ALL_STRATEGIES.to_vec();
         }
-        _ => panic!("Invalid Part :("),
+        return s.split(",").map(Strategy::from_str).collect();
+    }
+
+    fn from_config(part: u32) -> Self {
+        let default = if part == 1 { "per_block" } else { "first_fit" };
+        let name: String =
+            config::day_param(9, &format!("part{}_strategy", part), default.to_string());
+        return Strategy::from_str(&name);
     }
 }
 
-fn part1(input_file: &PathBuf) {
-    let input_file = File::open(input_file).expect(
-        format!(
-            "Could not open input file: {}",
-            input_file.to_string_lossy()
-        )
-        .as_str(),
-    );
+impl std::fmt::Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Strategy::PerBlock => "per_block",
+            Strategy::FirstFit => "first_fit",
+            Strategy::BestFit => "best_fit",
+            Strategy::WorstFit => "worst_fit",
+            Strategy::LargestFirst => "largest_first",
+            Strategy::FewestMoves => "fewest_moves",
+        };
+        return f.pad(name);
+    }
+}
 
-    let lines = BufReader::new(input_file).lines();
-    let mut diskmap: Option<Vec<i32>> = None;
-    for line in lines {
-        let line = line.expect("Could not read line");
-        diskmap = Some(
-            line.chars()
-                .into_iter()
-                .map(|c| c.to_digit(10).unwrap() as i32)
-                .collect(),
-        );
-        break;
+#[derive(Debug, Default)]
+struct Compaction {
+    moves: usize,        // Number of contiguous writes
+    blocks_moved: usize, // Number of blocks written
+}
+
+#[derive(Debug, Default)]
+struct Fragmentation {
+    holes: usize,            // Free runs before the last used block
+    hole_blocks: usize,      // Free blocks before the last used block
+    largest_hole: usize,     // Longest of those free runs
+    fragmented_files: usize, // Files split over more than one run
+}
+
+// Runs of file blocks that fill exactly the gaps next to them, as (start, len).
+#[derive(Debug, Default)]
+struct MoveGroup {
+    sources: Vec<(usize, usize)>,
+    gaps: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+struct DiskMap {
+    // One entry per block, the file id or None if the block is free.
+    blocks: Vec<Option<usize>>,
+}
+
+impl DiskMap {
+    fn parse(line: &str) -> Self {
+        let mut blocks: Vec<Option<usize>> = Vec::new();
+        for (idx, c) in line.trim().chars().enumerate() {
+            let size = c
+                .to_digit(10)
+                .expect(format!("Invalid size in diskmap: {}", c).as_str());
+            let block = if idx % 2 == 0 { Some(idx / 2) } else { None };
+            blocks.extend(std::iter::repeat_n(block, size as usize));
+        }
+        return DiskMap { blocks };
     }
 
-    let diskmap: Vec<i32> = diskmap.unwrap();
-    println!("Diskmap: {:?}", diskmap);
-
-    // Each entry is now "(size, id)", where id for empty blocks is 0
-    let diskmap: Vec<(i32, i32)> = diskmap
-        .into_iter()
-        .enumerate()
-        .filter(|(_, v)| *v != 0)
-        .map(|(idx, v)| {
-            if idx % 2 == 0 {
-                (v, idx as i32 / 2)
-            } else {
-                (v, -1)
+    // Runs of "(size, id)", where id for empty blocks is -1
+    fn runs(&self) -> Vec<(i32, i32)> {
+        let mut runs: Vec<(i32, i32)> = Vec::new();
+        for block in self.blocks.iter() {
+            let id = block.map_or(-1, |id| id as i32);
+            match runs.last_mut() {
+                Some((size, last_id)) if *last_id == id => *size += 1,
+                _ => runs.push((1, id)),
             }
-        })
-        .collect();
+        }
+        return runs;
+    }
 
-    println!("[(size, id)]: {:?}", diskmap);
+    // Contiguous (start, len) of each file id, only valid while files are unsplit.
+    fn file_spans(&self) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for (pos, block) in self.blocks.iter().enumerate() {
+            if let Some(id) = block {
+                // Empty files get a zero length span where the next file starts
+                while spans.len() <= *id {
+                    spans.push((pos, 0));
+                }
+                spans[*id].1 += 1;
+            }
+        }
+        return spans;
+    }
 
-    let mut defragged_diskmap: Vec<(i32, i32)> = Vec::new();
+    fn free_spans(&self) -> BTreeMap<usize, usize> {
+        let mut spans: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut start: Option<usize> = None;
+        for (pos, block) in self
+            .blocks
+            .iter()
+            .chain(std::iter::once(&Some(0)))
+            .enumerate()
+        {
+            match (block, start) {
+                (None, None) => start = Some(pos),
+                (Some(_), Some(s)) => {
+                    spans.insert(s, pos - s);
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        return spans;
+    }
 
-    let mut last_filled_ptr = last_full_block_before_idx(diskmap.len() as i32, &diskmap);
-    let (mut last_filled_size, mut last_filled_id) = diskmap[last_filled_ptr.unwrap()];
+    fn checksum(&self) -> i64 {
+        return self
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, block)| block.map(|id| pos as i64 * id as i64))
+            .sum();
+    }
 
-    let mut idx = 0;
-    let (mut size, mut id) = diskmap[idx];
-    while idx < diskmap.len() {
-        if last_filled_ptr.is_none() || idx >= last_filled_ptr.unwrap() {
-            break;
+    fn fragmentation(&self) -> Fragmentation {
+        let mut fragmentation = Fragmentation::default();
+        let end = self
+            .blocks
+            .iter()
+            .rposition(|b| b.is_some())
+            .map_or(0, |p| p + 1);
+        for (_, len) in self.free_spans().range(..end) {
+            fragmentation.holes += 1;
+            fragmentation.hole_blocks += len;
+            fragmentation.largest_hole = fragmentation.largest_hole.max(*len);
         }
 
-        if id != -1 {
-            defragged_diskmap.push((size, id));
-            idx += 1;
-            (size, id) = diskmap[idx];
-            continue;
+        let mut file_runs: BTreeMap<i32, usize> = BTreeMap::new();
+        for (_, id) in self.runs().iter().filter(|(_, id)| *id != -1) {
+            *file_runs.entry(*id).or_default() += 1;
         }
+        fragmentation.fragmented_files = file_runs.values().filter(|runs| **runs > 1).count();
+        return fragmentation;
+    }
 
-        if size == last_filled_size {
-            defragged_diskmap.push((size, last_filled_id));
-            last_filled_ptr = last_full_block_before_idx(last_filled_ptr.unwrap() as i32, &diskmap);
-            if last_filled_ptr.is_none() {
-                break;
+    fn compact(&mut self, strategy: Strategy) -> Compaction {
+        return match strategy {
+            Strategy::PerBlock => self.compact_blocks(),
+            Strategy::FewestMoves => {
+                let groups = self.plan_fewest_moves().unwrap_or_else(|count| {
+                    panic!(
+                        "fewest_moves can search at most {} files and gaps without a same size \
+                         match, this disk has {}",
+                        MAX_FEWEST_MOVES_SEARCH, count
+                    )
+                });
+                self.apply_move_groups(&groups)
             }
-            (last_filled_size, last_filled_id) = diskmap[last_filled_ptr.unwrap()];
+            _ => self.compact_files(strategy),
+        };
+    }
 
-            idx += 1;
-            (size, id) = diskmap[idx];
-            continue;
+    fn compact_blocks(&mut self) -> Compaction {
+        let mut compaction = Compaction::default();
+        if self.blocks.is_empty() {
+            return compaction;
         }
 
-        if size < last_filled_size {
-            defragged_diskmap.push((size, last_filled_id));
-            last_filled_size = last_filled_size - size;
+        let mut left = 0;
+        let mut right = self.blocks.len() - 1;
+        while left < right {
+            if self.blocks[left].is_some() {
+                left += 1;
+            } else if self.blocks[right].is_none() {
+                right -= 1;
+            } else {
+                self.blocks.swap(left, right);
+                compaction.moves += 1;
+                compaction.blocks_moved += 1;
+            }
+        }
+        return compaction;
+    }
+
+    fn compact_files(&mut self, strategy: Strategy) -> Compaction {
+        let mut compaction = Compaction::default();
+        let files = self.file_spans();
+        let mut free = self.free_spans();
 
-            idx += 1;
-            (size, id) = diskmap[idx];
-            continue;
+        let mut order: Vec<usize> = (0..files.len()).rev().collect();
+        // Just a heuristic, placing the big files first often leaves fewer holes but it doesn't
+        // promise fewer moves than the other strategies.
+        if strategy == Strategy::LargestFirst {
+            order.sort_by_key(|id| std::cmp::Reverse((files[*id].1, *id)));
         }
 
-        // size must be > filled_size
-        defragged_diskmap.push((last_filled_size, last_filled_id));
-        last_filled_ptr = last_full_block_before_idx(last_filled_ptr.unwrap() as i32, &diskmap);
-        size = size - last_filled_size;
-        if last_filled_ptr.is_none() {
-            break;
+        for id in order {
+            let (start, len) = files[id];
+            if len == 0 {
+                continue;
+            }
+            let gap = pick_gap(&free, strategy, start, len);
+            let Some((gap_start, gap_len)) = gap else {
+                continue;
+            };
+
+            for pos in 0..len {
+                self.blocks[gap_start + pos] = Some(id);
+                self.blocks[start + pos] = None;
+            }
+            free.remove(&gap_start);
+            if gap_len > len {
+                free.insert(gap_start + len, gap_len - len);
+            }
+            release_span(&mut free, start, len);
+
+            compaction.moves += 1;
+            compaction.blocks_moved += len;
         }
-        (last_filled_size, last_filled_id) = diskmap[last_filled_ptr.unwrap()];
-    }
-    if last_filled_ptr.is_some() && idx == last_filled_ptr.unwrap() {
-        defragged_diskmap.push((last_filled_size, last_filled_id));
+        return compaction;
     }
 
-    println!("Defragged: {:?}", defragged_diskmap);
-    println!(
-        "Checksum: {}",
-        format!("{}", calculate_checksum(&defragged_diskmap))
-            .as_str()
-            .green()
-            .bold()
-    );
-}
+    // Packs every file block to the front like per block. Only the file blocks past the packed
+    // end have to move, into the gaps before it, and each move takes part of one file run into
+    // part of one gap. Split the runs and gaps into groups with matching sizes and each group
+    // needs (runs + gaps - 1) moves, so the fewest moves come from the most groups. A run and a
+    // gap of the same size can always be a group of their own, the rest are searched exactly.
+    // Fails with the number left to search when there are too many of them.
+    fn plan_fewest_moves(&self) -> Result<Vec<MoveGroup>, usize> {
+        let used = self.blocks.iter().filter(|b| b.is_some()).count();
+        let mut sources: Vec<(usize, usize)> = Vec::new();
+        for (pos, block) in self.blocks.iter().enumerate().skip(used) {
+            if block.is_none() {
+                continue;
+            }
+            match sources.last_mut() {
+                Some((start, len)) if *start + *len == pos && self.blocks[*start] == *block => {
+                    *len += 1;
+                }
+                _ => sources.push((pos, 1)),
+            }
+        }
+        let gaps: Vec<(usize, usize)> = self
+            .free_spans()
+            .range(..used)
+            .map(|(start, len)| (*start, (*len).min(used - start)))
+            .collect();
+
+        let mut groups: Vec<MoveGroup> = Vec::new();
+        let mut unmatched_sources = sources.clone();
+        let mut unmatched_gaps: Vec<(usize, usize)> = Vec::new();
+        for gap in gaps.iter() {
+            match unmatched_sources.iter().rposition(|s| s.1 == gap.1) {
+                Some(idx) => groups.push(MoveGroup {
+                    sources: vec![unmatched_sources.remove(idx)],
+                    gaps: vec![*gap],
+                }),
+                None => unmatched_gaps.push(*gap),
+            }
+        }
 
-fn calculate_checksum(defragged_diskmap: &Vec<(i32, i32)>) -> i64 {
-    let mut num_blocks: i64 = 0;
-    let mut checksum: i64 = 0;
+        let items: Vec<((usize, usize), bool)> = unmatched_sources
+            .iter()
+            .map(|s| (*s, true))
+            .chain(unmatched_gaps.iter().map(|g| (*g, false)))
+            .collect();
+        if items.len() > MAX_FEWEST_MOVES_SEARCH {
+            return Err(items.len());
+        }
 
-    for (size, id) in defragged_diskmap {
-        if *id == -1 {
-            num_blocks += *size as i64;
-            continue;
+        // most_groups[mask] is the most groups the items in mask split into, found by adding
+        // one item at a time and closing a group whenever the sizes even out.
+        let size = |(span, is_source): &((usize, usize), bool)| -> i64 {
+            return if *is_source {
+                span.1 as i64
+            } else {
+                -(span.1 as i64)
+            };
+        };
+        let num_masks = 1usize << items.len();
+        let mut sum: Vec<i64> = vec![0; num_masks];
+        let mut most_groups: Vec<usize> = vec![0; num_masks];
+        let mut last_item: Vec<usize> = vec![0; num_masks];
+        for mask in 1..num_masks {
+            let lowest = mask.trailing_zeros() as usize;
+            sum[mask] = sum[mask & (mask - 1)] + size(&items[lowest]);
+            for (idx, _) in items
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+            {
+                let groups = most_groups[mask ^ (1 << idx)];
+                if groups >= most_groups[mask] {
+                    most_groups[mask] = groups;
+                    last_item[mask] = idx;
+                }
+            }
+            most_groups[mask] += (sum[mask] == 0) as usize;
         }
 
-        let block_sum: i64 = (num_blocks..(num_blocks + (*size) as i64)).sum();
-        checksum += block_sum * (*id as i64);
-        num_blocks += *size as i64;
+        let mut group = MoveGroup::default();
+        let mut mask = num_masks - 1;
+        while mask != 0 {
+            if sum[mask] == 0 && !group.sources.is_empty() {
+                groups.push(std::mem::take(&mut group));
+            }
+            let (span, is_source) = items[last_item[mask]];
+            if is_source {
+                group.sources.push(span);
+            } else {
+                group.gaps.push(span);
+            }
+            mask ^= 1 << last_item[mask];
+        }
+        if !group.sources.is_empty() {
+            groups.push(group);
+        }
+        return Ok(groups);
     }
 
-    return checksum;
+    fn apply_move_groups(&mut self, groups: &Vec<MoveGroup>) -> Compaction {
+        let mut compaction = Compaction::default();
+        for group in groups.iter() {
+            let mut sources = group.sources.iter().copied();
+            let mut source = sources.next();
+            for (gap_start, gap_len) in group.gaps.iter() {
+                let mut filled = 0;
+                while filled < *gap_len {
+                    let (start, len) = source.expect("Group sizes don't add up");
+                    let count = len.min(gap_len - filled);
+                    for offset in 0..count {
+                        self.blocks[gap_start + filled + offset] = self.blocks[start + offset];
+                        self.blocks[start + offset] = None;
+                    }
+                    compaction.moves += 1;
+                    compaction.blocks_moved += count;
+                    filled += count;
+                    source = if count == len {
+                        sources.next()
+                    } else {
+                        Some((start + count, len - count))
+                    };
+                }
+            }
+        }
+        return compaction;
+    }
+}
+
+// Free span left of `before` that can hold `len` blocks, chosen as the strategy prefers.
+fn pick_gap(
+    free: &BTreeMap<usize, usize>,
+    strategy: Strategy,
+    before: usize,
+    len: usize,
+) -> Option<(usize, usize)> {
+    let mut candidates = free
+        .range(..before)
+        .filter(|(_, gap_len)| **gap_len >= len)
+        .map(|(start, gap_len)| (*start, *gap_len));
+
+    return match strategy {
+        Strategy::BestFit => candidates.min_by_key(|(start, gap_len)| (*gap_len, *start)),
+        Strategy::WorstFit => {
+            candidates.min_by_key(|(start, gap_len)| (std::cmp::Reverse(*gap_len), *start))
+        }
+        _ => candidates.next(),
+    };
 }
 
-fn last_full_block_before_idx(idx: i32, diskmap: &Vec<(i32, i32)>) -> Option<usize> {
-    let idx = diskmap.len() as i32 - 1 - idx;
-    let num_to_skip = idx + 1;
-    return diskmap
-        .iter()
-        .enumerate()
-        .rev()
-        .skip(num_to_skip as usize)
-        .filter(|(_, (_, id))| *id != -1)
-        .map(|(idx, _)| idx)
-        .next();
+// Marks a span free again, merging it with the free spans on either side.
+fn release_span(free: &mut BTreeMap<usize, usize>, start: usize, len: usize) {
+    let mut start = start;
+    let mut len = len;
+    if let Some((prev_start, prev_len)) = free.range(..start).next_back() {
+        if prev_start + prev_len == start {
+            start = *prev_start;
+            len += prev_len;
+        }
+    }
+    if let Some(next_len) = free.remove(&(start + len)) {
+        len += next_len;
+    }
+    free.insert(start, len);
 }
 
-fn part2(input_file: &PathBuf) {
+fn read_disk_map(input_file: &PathBuf) -> DiskMap {
     let input_file = File::open(input_file).expect(
         format!(
             "Could not open input file: {}",
@@ -177,140 +448,133 @@ fn part2(input_file: &PathBuf) {
         .as_str(),
     );
 
-    let lines = BufReader::new(input_file).lines();
-    let mut diskmap: Option<Vec<i32>> = None;
-    for line in lines {
-        let line = line.expect("Could not read line");
-        diskmap = Some(
-            line.chars()
-                .into_iter()
-                .map(|c| c.to_digit(10).unwrap() as i32)
-                .collect(),
-        );
-        break;
-    }
+    let line = BufReader::new(input_file)
+        .lines()
+        .next()
+        .expect("Input file is empty")
+        .expect("Could not read line");
+    return DiskMap::parse(&line);
+}
 
-    let diskmap: Vec<i32> = diskmap.unwrap();
-    println!("Diskmap: {:?}", diskmap);
-
-    // Each entry is now "(size, id, has_moved)", where id for empty blocks is 0
-    let diskmap: Vec<(i32, i32, bool)> = diskmap
-        .into_iter()
-        .enumerate()
-        .filter(|(_, v)| *v != 0)
-        .map(|(idx, v)| {
-            if idx % 2 == 0 {
-                (v, idx as i32 / 2)
-            } else {
-                (v, -1)
+fn print_comparison(diskmap: &DiskMap, strategies: &Vec<Strategy>) {
+    println!(
+        "{:<14}{:>18}{:>10}{:>14}{:>8}{:>13}{:>14}{:>12}",
+        "strategy",
+        "checksum",
+        "moves",
+        "blocks moved",
+        "holes",
+        "hole blocks",
+        "largest hole",
+        "fragmented"
+    );
+    for strategy in strategies.iter() {
+        if *strategy == Strategy::FewestMoves {
+            if let Err(count) = diskmap.plan_fewest_moves() {
+                println!(
+                    "{:<14}too many files and gaps to search ({})",
+                    strategy, count
+                );
+                continue;
             }
-        })
-        .map(|(size, id)| (size, id, false))
-        .collect();
-
-    println!("[(size, id)]: {:?}", diskmap);
-
-    let mut defragged_diskmap: Vec<(i32, i32, bool)> = diskmap.clone();
-
-    let mut idx = (defragged_diskmap.len() - 1) as i32;
-    while idx >= 0 {
-        if defragged_diskmap[idx as usize].1 == -1 {
-            idx -= 1;
-            continue;
-        }
-
-        let (fill_size, fill_id, moved) = defragged_diskmap[idx as usize];
-        if moved {
-            idx -= 1;
-            continue;
         }
+        let mut compacted = diskmap.clone();
+        let compaction = compacted.compact(*strategy);
+        let fragmentation = compacted.fragmentation();
+        println!(
+            "{:<14}{:>18}{:>10}{:>14}{:>8}{:>13}{:>14}{:>12}",
+            strategy,
+            compacted.checksum(),
+            compaction.moves,
+            compaction.blocks_moved,
+            fragmentation.holes,
+            fragmentation.hole_blocks,
+            fragmentation.largest_hole,
+            fragmentation.fragmented_files
+        );
+    }
+    println!();
+}
 
-        let empty_idx = first_empty_idx(&defragged_diskmap, fill_size, idx as usize);
-        if empty_idx.is_none() {
-            idx -= 1;
-            continue;
-        }
-        let empty_idx = empty_idx.unwrap();
-        let empty_size = defragged_diskmap[empty_idx].0;
-
-        defragged_diskmap[empty_idx] = (fill_size, fill_id, true);
-
-        if empty_size > fill_size {
-            let remaining_size = empty_size - fill_size;
-            defragged_diskmap.insert(empty_idx + 1, (remaining_size, -1, false));
-            idx += 1;
-        }
+fn run(input_file: &PathBuf, part: u32) -> Explanation {
+    let mut diskmap = read_disk_map(input_file);
+    println!("[(size, id)]: {:?}", diskmap.runs());
 
-        let block_removed = insert_empty_block(&mut defragged_diskmap, fill_size, idx as usize);
-        idx -= if block_removed { 1 } else { 0 };
+    let compare: String = config::day_param(9, "compare", String::new());
+    if !compare.is_empty() {
+        print_comparison(&diskmap, &Strategy::parse_list(&compare));
+    }
 
-        idx -= 1;
+    let strategy = Strategy::from_config(part);
+    let compaction = diskmap.compact(strategy);
+    let fragmentation = diskmap.fragmentation();
+    if strategy == Strategy::PerBlock {
+        metrics::add("blocks_moved", compaction.blocks_moved as u64);
+    } else {
+        metrics::add("files_moved", compaction.moves as u64);
     }
+    println!(
+        "Strategy {}: {} moves, {} blocks moved",
+        strategy, compaction.moves, compaction.blocks_moved
+    );
+    println!(
+        "Fragmentation: {} holes ({} blocks, largest {}), {} fragmented files",
+        fragmentation.holes,
+        fragmentation.hole_blocks,
+        fragmentation.largest_hole,
+        fragmentation.fragmented_files
+    );
 
+    let defragged_diskmap = diskmap.runs();
     println!("Defragged: {:?}", defragged_diskmap);
-    let defragged_diskmap: Vec<(i32, i32)> =
-        defragged_diskmap.iter().map(|(s, i, _)| (*s, *i)).collect();
+    let checksum = diskmap.checksum();
     println!(
         "Checksum: {}",
-        format!("{}", calculate_checksum(&defragged_diskmap))
-            .as_str()
-            .green()
-            .bold()
+        format!("{}", checksum).as_str().green().bold()
     );
-}
 
-fn first_empty_idx(
-    diskmap: &Vec<(i32, i32, bool)>,
-    min_size: i32,
-    max_idx: usize,
-) -> Option<usize> {
-    return diskmap
-        .iter()
-        .enumerate()
-        .filter(|(idx, (size, id, _))| *id == -1 && *size >= min_size && *idx <= max_idx)
-        .map(|(idx, _)| idx)
-        .next();
+    let mut explanation = Explanation::default();
+    explanation.step(
+        format!("Compaction ({})", strategy),
+        format!(
+            "{} moves, {} blocks moved",
+            compaction.moves, compaction.blocks_moved
+        ),
+    );
+    explanation.step(
+        "Fragmentation",
+        format!(
+            "{} holes, {} fragmented files",
+            fragmentation.holes, fragmentation.fragmented_files
+        ),
+    );
+    explain_checksum(&defragged_diskmap, &mut explanation);
+    return explanation.answer(checksum);
 }
 
-fn insert_empty_block(diskmap: &mut Vec<(i32, i32, bool)>, size: i32, idx: usize) -> bool {
-    // Returns true if a block was removed to the left of idx.
-    if idx == 0 && diskmap[idx + 1].1 == -1 {
-        let removed_size = diskmap[idx + 1].0;
-        diskmap[idx] = (size + removed_size, -1, false);
-        let _ = diskmap.remove(idx + 1);
-        return false;
-    }
-
-    if idx == diskmap.len() - 1 && diskmap[idx - 1].1 == -1 {
-        let removed_size = diskmap[idx - 1].0;
-        diskmap[idx - 1] = (size + removed_size, -1, false);
-        let _ = diskmap.remove(idx).0;
-        return true;
-    }
-
-    if idx > 0 && diskmap[idx - 1].1 == -1 && idx < diskmap.len() - 1 && diskmap[idx + 1].1 == -1 {
-        let (removed_1, _, _) = diskmap[idx - 1];
-        let (removed_2, _, _) = diskmap[idx + 1];
-        diskmap[idx] = (size + removed_1 + removed_2, -1, false);
-        let _ = diskmap.remove(idx + 1);
-        let _ = diskmap.remove(idx - 1).0;
-        return true;
-    }
+fn part1(input_file: &PathBuf) -> Explanation {
+    return run(input_file, 1);
+}
 
-    if idx > 0 && diskmap[idx - 1].1 == -1 {
-        let (removed_size, _, _) = diskmap[idx - 1];
-        diskmap[idx] = (size + removed_size, -1, false);
-        let _ = diskmap.remove(idx - 1).0;
-        return true;
-    }
+fn part2(input_file: &PathBuf) -> Explanation {
+    return run(input_file, 2);
+}
 
-    if idx < diskmap.len() - 1 && diskmap[idx + 1].1 == -1 {
-        let (removed_size, _, _) = diskmap[idx + 1];
-        diskmap[idx] = (size + removed_size, -1, false);
-        let _ = diskmap.remove(idx + 1).0;
-        return false;
+fn explain_checksum(defragged_diskmap: &Vec<(i32, i32)>, explanation: &mut Explanation) {
+    let mut num_blocks: i64 = 0;
+    for (size, id) in defragged_diskmap {
+        if *id != -1 {
+            let block_sum: i64 = (num_blocks..(num_blocks + (*size) as i64)).sum();
+            explanation.step(
+                format!(
+                    "File {} at blocks {}..{}",
+                    id,
+                    num_blocks,
+                    num_blocks + *size as i64
+                ),
+                format!("{} x {} = {}", block_sum, id, block_sum * (*id as i64)),
+            );
+        }
+        num_blocks += *size as i64;
     }
-
-    diskmap[idx] = (size, -1, false);
-    return false;
 }
@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+// Structured description of how a part arrived at its answer. Every part returns one of these,
+// and the runner decides whether (and how) to render it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Explanation {
+    pub answer: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Step {
+    pub subject: String,
+    pub detail: String,
+}
+
+impl Explanation {
+    pub fn step(&mut self, subject: impl ToString, detail: impl ToString) {
+        self.steps.push(Step {
+            subject: subject.to_string(),
+            detail: detail.to_string(),
+        });
+    }
+
+    pub fn answer(mut self, answer: impl ToString) -> Self {
+        self.answer = answer.to_string();
+        return self;
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut ret = format!("Answer: {}\n", self.answer);
+        for step in self.steps.iter() {
+            ret += format!("  {} => {}\n", step.subject, step.detail).as_str();
+        }
+        return ret;
+    }
+}
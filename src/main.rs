@@ -1,4 +1,6 @@
 use clap::Parser;
+mod cancel;
+mod config;
 mod day1;
 mod day10;
 mod day11;
@@ -22,6 +24,10 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
+mod explain;
+mod memory;
+mod metrics;
+mod runner;
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -35,10 +41,114 @@ struct Args {
     // Overriden Input
     #[arg(short, long)]
     input_file: Option<std::path::PathBuf>,
+
+    // Config file, defaults to ./aoc.toml if present
+    #[arg(short, long)]
+    config: Option<std::path::PathBuf>,
+
+    #[arg(short, long, overrides_with = "no_verbose")]
+    verbose: bool,
+
+    // Turn verbose off even if the config file turns it on
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    #[arg(long, value_enum)]
+    color: Option<config::ColorMode>,
+
+    // Explain how each answer was derived
+    #[arg(short, long, overrides_with = "no_explain")]
+    explain: bool,
+
+    // Turn explanations off even if the config file turns them on
+    #[arg(long, overrides_with = "explain")]
+    no_explain: bool,
+
+    #[arg(short, long, value_enum)]
+    format: Option<config::OutputFormat>,
+
+    // Write the JSON reports to this file instead of stderr
+    #[arg(long)]
+    json_file: Option<std::path::PathBuf>,
+
+    // Abort the run once the heap grows past this, eg. 512M or 2G
+    #[arg(long)]
+    max_memory: Option<String>,
+
+    // Stop each part after this many seconds, reporting partial progress
+    #[arg(short, long)]
+    timeout: Option<f64>,
+
+    // Day 1: list comparison metrics to report, eg. emd,jaccard or all
+    #[arg(long)]
+    metric: Option<String>,
+
+    // Day 7: operators to try in both parts, eg. add,mul,concat2
+    #[arg(long, value_delimiter = ',')]
+    operators: Vec<String>,
+
+    // Day 8: antinode distance ratios for part 1, eg. 2:1,3:1
+    #[arg(long, value_delimiter = ',')]
+    ratio: Vec<String>,
+
+    // Day 8: only draw antinodes of these frequencies, eg. A or Aa0
+    #[arg(long)]
+    freq: Option<String>,
+
+    // Per-day parameter overrides, eg. --set day14.board_size=[11,7]
+    #[arg(short, long = "set", value_name = "DAYN.KEY=VALUE")]
+    set: Vec<String>,
 }
 
 fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    let mut config = config::Config::load(args.config.as_ref());
+    if args.verbose || args.no_verbose {
+        config.verbose = args.verbose;
+    }
+    config.color = args.color.unwrap_or(config.color);
+    if args.explain || args.no_explain {
+        config.explain = args.explain;
+    }
+    config.format = args.format.unwrap_or(config.format);
+    config.json_file = args.json_file.or(config.json_file);
+    if let Some(path) = &config.json_file {
+        // Each part appends its report, so start every run from an empty file.
+        std::fs::File::create(path)
+            .expect(format!("Could not create {}", path.to_string_lossy()).as_str());
+    }
+    config.max_memory = args.max_memory.or(config.max_memory);
+    config.timeout = args.timeout.or(config.timeout);
+    if let Some(metric) = args.metric {
+        config.set_param(1, "metric", toml::Value::String(metric));
+    }
+    if !args.operators.is_empty() {
+        let operators: Vec<toml::Value> = args
+            .operators
+            .iter()
+            .map(|op| toml::Value::String(op.clone()))
+            .collect();
+        config.set_param(7, "part1_operators", toml::Value::Array(operators.clone()));
+        config.set_param(7, "part2_operators", toml::Value::Array(operators));
+    }
+    if !args.ratio.is_empty() {
+        let ratios = args
+            .ratio
+            .iter()
+            .map(|r| toml::Value::String(r.clone()))
+            .collect();
+        config.set_param(8, "ratios", toml::Value::Array(ratios));
+    }
+    if let Some(freq) = args.freq {
+        config.set_param(8, "freq", toml::Value::String(freq));
+    }
+    for spec in args.set.iter() {
+        config.apply_override(spec);
+    }
+    args.part = args.part.or(config.part);
+    memory::set_limit(config.max_memory.as_deref().map(memory::parse_size));
+    config::init(config);
 
     match args.day {
         1 => day1::main(args.part, args.input_file),
@@ -0,0 +1,108 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io::Write,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+// Wraps the system allocator to keep track of how much heap is in use, so the runner can
+// report peak memory per part and stop a run that goes over --max-memory.
+pub struct CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+static LIMIT_EXCEEDED: AtomicBool = AtomicBool::new(false);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        track_allocation(layout.size());
+        return System.alloc(layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        track_allocation(layout.size());
+        return System.alloc_zeroed(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > layout.size() {
+            track_allocation(new_size - layout.size());
+        } else {
+            CURRENT.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+        }
+        return System.realloc(ptr, layout, new_size);
+    }
+}
+
+fn track_allocation(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+
+    if current > LIMIT.load(Ordering::Relaxed) && !LIMIT_EXCEEDED.swap(true, Ordering::Relaxed) {
+        // We're inside the allocator, so nothing here may allocate. Formatting numbers into
+        // stderr is fine, and any allocation made while exiting skips this branch because
+        // LIMIT_EXCEEDED is already set.
+        let _ = writeln!(
+            std::io::stderr(),
+            "\nMemory limit exceeded: {} in use, limit is {}. Aborting run.",
+            Bytes(current),
+            Bytes(LIMIT.load(Ordering::Relaxed))
+        );
+        std::process::exit(2);
+    }
+}
+
+pub fn set_limit(limit: Option<usize>) {
+    LIMIT.store(limit.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+pub fn peak() -> usize {
+    return PEAK.load(Ordering::Relaxed);
+}
+
+pub fn parse_size(size: &str) -> usize {
+    // Accepts plain byte counts, or K/M/G suffixed sizes (powers of 1024), eg. "512M".
+    let size = size.trim().to_uppercase();
+    let size = size.trim_end_matches("IB").trim_end_matches("B");
+    let (digits, multiplier) = match size.chars().last() {
+        Some('K') => (&size[..size.len() - 1], 1 << 10),
+        Some('M') => (&size[..size.len() - 1], 1 << 20),
+        Some('G') => (&size[..size.len() - 1], 1 << 30),
+        _ => (size, 1),
+    };
+
+    let value: f64 = digits
+        .trim()
+        .parse()
+        .expect(format!("Invalid memory size: {}", size).as_str());
+    return (value * multiplier as f64) as usize;
+}
+
+// Human readable byte count, eg. "12.5 MiB".
+pub struct Bytes(pub usize);
+
+impl std::fmt::Display for Bytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = self.0 as f64;
+        if bytes >= (1 << 30) as f64 {
+            return write!(f, "{:.2} GiB", bytes / (1 << 30) as f64);
+        } else if bytes >= (1 << 20) as f64 {
+            return write!(f, "{:.2} MiB", bytes / (1 << 20) as f64);
+        } else if bytes >= (1 << 10) as f64 {
+            return write!(f, "{:.2} KiB", bytes / (1 << 10) as f64);
+        }
+        return write!(f, "{} B", self.0);
+    }
+}
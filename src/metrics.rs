@@ -0,0 +1,19 @@
+use std::{cell::RefCell, collections::BTreeMap};
+
+// Lightweight per-part counters. Solvers bump these wherever they do interesting work, and
+// the runner drains them after each part.
+thread_local! {
+    static COUNTERS: RefCell<BTreeMap<&'static str, u64>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+pub fn incr(name: &'static str) {
+    add(name, 1);
+}
+
+pub fn add(name: &'static str, count: u64) {
+    COUNTERS.with(|counters| *counters.borrow_mut().entry(name).or_insert(0) += count);
+}
+
+pub fn take() -> BTreeMap<&'static str, u64> {
+    return COUNTERS.with(|counters| std::mem::take(&mut *counters.borrow_mut()));
+}
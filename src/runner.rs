@@ -0,0 +1,158 @@
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    cancel,
+    config::{self, OutputFormat},
+    explain::{Explanation, Step},
+    memory::{self, Bytes},
+    metrics,
+};
+
+// How long a solver gets to notice cancellation before the watchdog exits the process.
+static WATCHDOG_GRACE: Duration = Duration::from_secs(5);
+
+// Bumped whenever a part finishes, so watchdogs of earlier parts know to stand down.
+static PARTS_FINISHED: AtomicU64 = AtomicU64::new(0);
+
+// Everything the runner knows about one part once it is done.
+#[derive(Debug, Serialize)]
+struct PartReport {
+    day: u32,
+    part: u32,
+    answer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    explanation: Option<Vec<Step>>,
+    metrics: BTreeMap<&'static str, u64>,
+    peak_memory_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timed_out_after_states: Option<u64>,
+}
+
+pub fn run_parts<P1, P2>(day: u32, part_opt: Option<u32>, part1: P1, part2: P2)
+where
+    P1: FnOnce() -> Explanation,
+    P2: FnOnce() -> Explanation,
+{
+    if part_opt.is_none() {
+        run_part(day, 1, part1);
+        println!();
+        run_part(day, 2, part2);
+        return;
+    }
+
+    let part = part_opt.unwrap();
+    match part {
+        1 => run_part(day, 1, part1),
+        2 => run_part(day, 2, part2),
+        _ => panic!("Invalid Part :("),
+    }
+}
+
+fn run_part<P: FnOnce() -> Explanation>(day: u32, part: u32, solver: P) {
+    println!("Running Day {}, Part {}", day, part);
+    let config = config::get();
+    let timeout = config.timeout.map(Duration::from_secs_f64);
+    if let Some(timeout) = timeout {
+        spawn_watchdog(timeout);
+    }
+
+    let _ = metrics::take(); // Don't carry counters over from a previous part.
+    memory::reset_peak();
+    cancel::arm(timeout);
+    let start = Instant::now();
+
+    let explanation = solver();
+
+    let elapsed = start.elapsed();
+    let timed_out_after_states = cancel::disarm();
+    PARTS_FINISHED.fetch_add(1, Ordering::Relaxed);
+    let counters = metrics::take();
+    let peak_memory_bytes = memory::peak();
+
+    let report = PartReport {
+        day,
+        part,
+        answer: explanation.answer.clone(),
+        explanation: if config.explain {
+            Some(explanation.steps.clone())
+        } else {
+            None
+        },
+        metrics: counters,
+        peak_memory_bytes,
+        timed_out_after_states,
+    };
+
+    match config.format {
+        OutputFormat::Text => {
+            if let Some(states) = timed_out_after_states {
+                println!();
+                println!(
+                    "{}",
+                    format!(
+                        "Timed out after {} states ({:.1}s), partial answer: {}",
+                        states,
+                        elapsed.as_secs_f64(),
+                        explanation.answer
+                    )
+                    .as_str()
+                    .yellow()
+                    .bold()
+                );
+            }
+            if config.verbose {
+                println!();
+                println!("Metrics for Day {}, Part {}:", day, part);
+                for (name, count) in report.metrics.iter() {
+                    println!("  {:<24} {}", name, count);
+                }
+                println!("  {:<24} {}", "peak_memory", Bytes(peak_memory_bytes));
+            }
+            if config.explain {
+                println!();
+                println!("Explanation for Day {}, Part {}:", day, part);
+                print!("{}", explanation.render_text());
+            }
+        }
+        OutputFormat::Json => {
+            // Kept apart from stdout, where the solvers print as they go.
+            let json = serde_json::to_string(&report).expect("Could not serialize report");
+            match &config.json_file {
+                Some(path) => {
+                    let mut file = OpenOptions::new()
+                        .append(true)
+                        .open(path)
+                        .expect(format!("Could not open {}", path.to_string_lossy()).as_str());
+                    writeln!(file, "{}", json).expect("Could not write report");
+                }
+                None => eprintln!("{}", json),
+            }
+        }
+    }
+}
+
+fn spawn_watchdog(timeout: Duration) {
+    // Not every solver checks for cancellation (or it may be stuck waiting on stdin), so make
+    // sure a timed out run can't hang forever.
+    let parts_finished = PARTS_FINISHED.load(Ordering::Relaxed);
+    thread::spawn(move || {
+        thread::sleep(timeout + WATCHDOG_GRACE);
+        if PARTS_FINISHED.load(Ordering::Relaxed) == parts_finished {
+            eprintln!(
+                "Timed out after {:.1}s and the solver did not stop, exiting.",
+                timeout.as_secs_f64()
+            );
+            std::process::exit(124);
+        }
+    });
+}